@@ -0,0 +1,146 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::stmt::{function::FunctionStmt, Stmt};
+
+pub const AST_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct AstDocument {
+    pub version: u32,
+    pub statements: Vec<Stmt>,
+}
+
+pub fn to_json(stmts: &[Stmt]) -> serde_json::Result<String> {
+    let doc = AstDocument {
+        version: AST_VERSION,
+        statements: stmts.to_vec(),
+    };
+    serde_json::to_string_pretty(&doc)
+}
+
+pub fn from_json(source: &Bytes) -> serde_json::Result<Vec<Stmt>> {
+    let doc: AstDocument = serde_json::from_slice(source)?;
+    if doc.version != AST_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "unsupported AST version {}, expected {}",
+            doc.version, AST_VERSION
+        )));
+    }
+    check_class_refs(&doc.statements).map_err(serde::de::Error::custom)?;
+    Ok(doc.statements)
+}
+
+// 解析器只会在超类和 trait 的位置放变量，求值器和 Resolver 依赖这一点
+fn check_class_refs(stmts: &[Stmt]) -> Result<(), String> {
+    let functions = |functions: &[std::rc::Rc<FunctionStmt>]| {
+        functions
+            .iter()
+            .try_for_each(|function| check_class_refs(&function.fn_body))
+    };
+    for stmt in stmts {
+        match stmt {
+            Stmt::Class(class) => {
+                let name = &class.name;
+                if class
+                    .superclass
+                    .iter()
+                    .any(|expr| expr.to_variable().is_none())
+                {
+                    return Err(format!(
+                        "superclass of '{}' on line {} must be a variable",
+                        name.lexeme, name.line
+                    ));
+                }
+                if class.traits.iter().any(|expr| expr.to_variable().is_none()) {
+                    return Err(format!(
+                        "traits of '{}' on line {} must be variables",
+                        name.lexeme, name.line
+                    ));
+                }
+                functions(&class.methods)?;
+                functions(&class.class_methods)?;
+            }
+            Stmt::Trait(trait_stmt) => {
+                functions(&trait_stmt.methods)?;
+                functions(&trait_stmt.class_methods)?;
+            }
+            Stmt::Function(function) => check_class_refs(&function.fn_body)?,
+            Stmt::Block(block) => check_class_refs(&block.statements)?,
+            Stmt::If(if_stmt) => {
+                check_class_refs(std::slice::from_ref(&if_stmt.then_branch))?;
+                check_class_refs(if_stmt.else_branch.as_slice())?;
+            }
+            Stmt::While(while_stmt) => check_class_refs(std::slice::from_ref(&while_stmt.body))?,
+            Stmt::For(for_stmt) => check_class_refs(std::slice::from_ref(&for_stmt.body))?,
+            Stmt::DoWhile(do_while) => check_class_refs(std::slice::from_ref(&do_while.body))?,
+            Stmt::Var(_) | Stmt::Expression(_) | Stmt::Print(_) | Stmt::Return(_) => {}
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn round_trip() {
+    use crate::{parser::Parser, scanner::Scanner};
+
+    let src = Bytes::from(
+        r#"
+class A < B { init(x) { this.x = -x; } get() { return super.get() or nil; } }
+for (var i = 0; i < 3; i = i + 1) { if (!true) print "a" + "b"; else print 1.5; }
+"#,
+    );
+    let mut scanner = Scanner::new(src);
+    scanner.scan_tokens().unwrap();
+    let stmts = Parser::new(scanner.tokens()).parse();
+
+    let json = to_json(&stmts).unwrap();
+    let loaded = from_json(&Bytes::from(json.clone())).unwrap();
+    assert_eq!(json, to_json(&loaded).unwrap());
+}
+
+#[test]
+fn foreign_mixin_reports_runtime_error() {
+    use crate::{
+        evaluator::{Evaluator, InterpretError, Interprete},
+        test_util::parse_source,
+    };
+
+    // 手改过的 AST：trait 位置放的是字面量而不是变量
+    let mut stmts = parse_source("class A with T {}\n1;");
+    let Stmt::Expression(literal) = stmts.pop().unwrap() else {
+        unreachable!()
+    };
+    let Stmt::Class(class) = &mut stmts[0] else {
+        unreachable!()
+    };
+    class.traits[0] = literal.expression;
+
+    // from_json 会拒绝这样的 AST，求值器自己也不能因此崩掉
+    let result = Evaluator::new(false).eval(&stmts[0]);
+    assert!(matches!(result, Err(InterpretError::Runtime(msg)) if msg == "mixin must be a trait"));
+}
+
+#[test]
+fn foreign_superclass_is_rejected() {
+    use crate::test_util::parse_source;
+
+    // 超类位置放的是 `this`，类声明在函数里面
+    let mut stmts = parse_source("fun f() { class A < B {} }\nthis;");
+    let Stmt::Expression(this) = stmts.pop().unwrap() else {
+        unreachable!()
+    };
+    let Stmt::Function(function) = &mut stmts[0] else {
+        unreachable!()
+    };
+    let Stmt::Class(class) = &mut std::rc::Rc::get_mut(function).unwrap().fn_body[0] else {
+        unreachable!()
+    };
+    class.superclass = Some(this.expression);
+    let json = to_json(&stmts).unwrap();
+    let error = from_json(&Bytes::from(json)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "superclass of 'A' on line 1 must be a variable"
+    );
+}
@@ -1,4 +1,8 @@
-use crate::expr::{binary::BinaryExpr, Expr};
+use crate::{
+    expr::{binary::BinaryExpr, Expr},
+    stmt::{function::FunctionStmt, Stmt},
+    token::Token,
+};
 
 pub trait AstPrinter {
     fn print(&self, debug: bool) -> String;
@@ -26,14 +30,43 @@ impl AstPrinter for Expr {
             Expr::Unary(unary) => {
                 self.parenthesize(&unary.operator.lexeme, [&unary.right].to_vec(),debug)
             }
-            Expr::Variable(variable) => todo!(),
-            Expr::Assign(assign) => todo!(),
-            Expr::Logical(logical_expr) => todo!(),
-            Expr::Call(call_expr) => todo!(),
-            Expr::Get(get_expr) => todo!(),
-            Expr::Set(set_expr) => todo!(),
-            Expr::This(this_expr) => todo!(),
-            Expr::Super(super_expr) => todo!(),
+            Expr::Variable(variable) => variable.name.lexeme.clone(),
+            Expr::Assign(assign) => {
+                let name = format!("= {}", assign.name.lexeme);
+                self.parenthesize(&name, [&assign.value].to_vec(), debug)
+            }
+            Expr::Logical(logical) => self.parenthesize(
+                &logical.operator.lexeme,
+                [&logical.left, &logical.right].to_vec(),
+                debug,
+            ),
+            Expr::Call(call) => {
+                let mut exprs = vec![&call.callee];
+                exprs.extend(call.arguments.iter());
+                let name = if call.optional { "call?" } else { "call" };
+                let mut s = self.parenthesize(name, exprs, debug);
+                for arg in &call.named {
+                    s.pop();
+                    s.push_str(&format!(" {}: {})", arg.name.lexeme, arg.value.print(debug)));
+                }
+                s
+            }
+            Expr::Get(get) => {
+                let dot = if get.optional { "?." } else { "." };
+                let name = format!("{} {}", dot, get.name.lexeme);
+                self.parenthesize(&name, [&get.object].to_vec(), debug)
+            }
+            Expr::Set(set) => {
+                let set_name = if set.optional { "set?" } else { "set" };
+                let name = format!("{} {}", set_name, set.name.lexeme);
+                self.parenthesize(&name, [&set.object, &set.value].to_vec(), debug)
+            }
+            Expr::Index(index) => {
+                self.parenthesize("[]", [&index.object, &index.index].to_vec(), debug)
+            }
+            Expr::Spread(spread) => self.parenthesize("...", [&spread.expression].to_vec(), debug),
+            Expr::This(_) => "this".to_string(),
+            Expr::Super(super_expr) => format!("super.{}", super_expr.method.lexeme),
         }
     }
 
@@ -47,3 +80,139 @@ impl AstPrinter for Expr {
         s
     }
 }
+
+impl AstPrinter for Stmt {
+    fn print(&self, debug: bool) -> String {
+        match self {
+            Stmt::Var(var) => {
+                let name = format!("var {}{}", var.name.lexeme, annotation(&var.type_ann));
+                match &var.initializer {
+                    Some(init) => self.parenthesize(&name, [init].to_vec(), debug),
+                    None => format!("({})", name),
+                }
+            }
+            Stmt::Expression(expr) => self.parenthesize(";", [&expr.expression].to_vec(), debug),
+            Stmt::Print(print) => self.parenthesize("print", [&print.expression].to_vec(), debug),
+            Stmt::Block(block) => parts("block", block.statements.iter().map(|s| s.print(debug))),
+            Stmt::If(if_stmt) => {
+                let mut v = vec![
+                    if_stmt.condition.print(debug),
+                    if_stmt.then_branch.print(debug),
+                ];
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    v.push(else_branch.print(debug));
+                }
+                parts("if", v)
+            }
+            Stmt::While(while_stmt) => parts(
+                "while",
+                [
+                    while_stmt.condition.print(debug),
+                    while_stmt.body.print(debug),
+                ],
+            ),
+            Stmt::For(for_stmt) => {
+                let init = for_stmt
+                    .initializer
+                    .as_ref()
+                    .map_or("nil".to_string(), |s| s.print(debug));
+                let condition = for_stmt
+                    .condition
+                    .as_ref()
+                    .map_or("nil".to_string(), |e| e.print(debug));
+                let increment = for_stmt
+                    .increment
+                    .as_ref()
+                    .map_or("nil".to_string(), |e| e.print(debug));
+                parts(
+                    "for",
+                    [init, condition, increment, for_stmt.body.print(debug)],
+                )
+            }
+            Stmt::DoWhile(do_while) => parts(
+                "do",
+                [do_while.body.print(debug), do_while.condition.print(debug)],
+            ),
+            Stmt::Function(function) => function_parts("fun", function, debug),
+            Stmt::Return(ret) => match &ret.value {
+                Some(value) => self.parenthesize("return", [value].to_vec(), debug),
+                None => "(return)".to_string(),
+            },
+            Stmt::Class(class) => {
+                let mut name = match class.superclass_name() {
+                    Some(superclass) => format!("class {} < {}", class.name.lexeme, superclass),
+                    None => format!("class {}", class.name.lexeme),
+                };
+                if !class.traits.is_empty() {
+                    let traits: Vec<_> = class.traits.iter().map(|t| t.print(debug)).collect();
+                    name.push_str(&format!(" with {}", traits.join(", ")));
+                }
+                let class_methods = class
+                    .class_methods
+                    .iter()
+                    .map(|m| function_parts("static", m, debug));
+                let fields = class.fields.iter().map(|f| {
+                    format!("(field {}{})", f.name.lexeme, annotation(&f.type_ann))
+                });
+                let methods = class.methods.iter().map(|m| function_parts("method", m, debug));
+                parts(&name, fields.chain(class_methods).chain(methods))
+            }
+            Stmt::Trait(trait_stmt) => {
+                let class_methods = trait_stmt
+                    .class_methods
+                    .iter()
+                    .map(|m| function_parts("static", m, debug));
+                let methods = trait_stmt
+                    .methods
+                    .iter()
+                    .map(|m| function_parts("method", m, debug));
+                let name = format!("trait {}", trait_stmt.name.lexeme);
+                parts(&name, class_methods.chain(methods))
+            }
+        }
+    }
+
+    fn parenthesize(&self, name: &str, exprs: Vec<&Expr>, debug: bool) -> String {
+        parts(name, exprs.iter().map(|e| e.print(debug)))
+    }
+}
+
+fn parts(name: &str, parts: impl IntoIterator<Item = String>) -> String {
+    let mut s = format!("({}", name);
+    for part in parts {
+        s.push(' ');
+        s.push_str(&part);
+    }
+    s.push(')');
+    s
+}
+
+fn function_parts(kind: &str, function: &FunctionStmt, debug: bool) -> String {
+    let mut params: Vec<_> = function
+        .params
+        .iter()
+        .map(|p| {
+            let name = format!("{}{}", p.name.lexeme, annotation(&p.type_ann));
+            match &p.default {
+                Some(default) => format!("{} = {}", name, default.print(debug)),
+                None => name,
+            }
+        })
+        .collect();
+    if let Some(rest) = &function.rest {
+        params.push(format!("...{}", rest.lexeme));
+    }
+    let return_type = annotation(&function.return_type);
+    let name = if function.getter {
+        format!("{} {}{}", kind, function.name.lexeme, return_type)
+    } else {
+        format!("{} {}({}){}", kind, function.name.lexeme, params.join(", "), return_type)
+    };
+    parts(&name, function.fn_body.iter().map(|s| s.print(debug)))
+}
+
+fn annotation(type_ann: &Option<Token>) -> String {
+    type_ann
+        .as_ref()
+        .map_or(String::new(), |t| format!(": {}", t.lexeme))
+}
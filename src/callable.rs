@@ -1,13 +1,61 @@
+use std::fmt::Display;
+
 use crate::{
     data_types::scaler::Scalar,
-    evaluator::{Evaluator, InterpretResult},
+    error::report_runtime,
+    evaluator::{Evaluator, InterpretError, InterpretResult},
+    token::Token,
+    InterpretRtErr,
 };
 
 pub trait Callable {
     fn to_string(&self) -> String {
         "<native fn>".to_string()
     }
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
 
     fn call(&self, evaluator: &mut Evaluator, args: Vec<Scalar>) -> InterpretResult<Scalar>;
+
+    // `f(1, name: 2)`，只有用户函数知道参数名
+    fn call_named(
+        &self,
+        _evaluator: &mut Evaluator,
+        _args: Vec<Scalar>,
+        _named: Vec<(Token, Scalar)>,
+        paren: &Token,
+    ) -> InterpretResult<Scalar> {
+        let message = format!("{} doesn't accept named arguments.", self.to_string());
+        report_runtime(paren.line, message);
+        InterpretRtErr!(;"named arguments")
+    }
+}
+
+// 可接受的参数个数，`max` 为 None 表示不限（rest 参数 / 变参 native）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+impl Arity {
+    pub fn exact(n: usize) -> Self {
+        Self {
+            min: n,
+            max: Some(n),
+        }
+    }
+    pub fn at_least(n: usize) -> Self {
+        Self { min: n, max: None }
+    }
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.map_or(true, |max| count <= max)
+    }
+}
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
 }
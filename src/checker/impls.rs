@@ -0,0 +1,345 @@
+use std::rc::Rc;
+
+use crate::{
+    data_types::scaler::Scalar,
+    error::my_error_token,
+    expr::{call::CallExpr, Expr},
+    stmt::{function::FunctionStmt, Stmt},
+    token::Token,
+    token_type::TokenType,
+};
+
+use super::{Checker, Type};
+
+impl Checker {
+    pub(super) fn check_stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Var(var) => {
+                let declared = self.annotation(var.type_ann.as_ref(), true);
+                if let Some(initializer) = &var.initializer {
+                    let t = self.type_of(initializer);
+                    if !self.assignable(&t, &declared) {
+                        let message = format!(
+                            "Type mismatch: '{}' is {} but the initializer is {}.",
+                            var.name.lexeme, declared, t
+                        );
+                        my_error_token(var.name.clone(), message);
+                    }
+                }
+                self.define(&var.name.lexeme, declared);
+            }
+            Stmt::Expression(expr) => {
+                self.type_of(&expr.expression);
+            }
+            Stmt::Print(print) => {
+                self.type_of(&print.expression);
+            }
+            Stmt::Block(block) => {
+                self.begin_scope();
+                self.check_stmts(&block.statements);
+                self.end_scope();
+            }
+            Stmt::If(if_stmt) => {
+                self.type_of(&if_stmt.condition);
+                self.check_stmt(&if_stmt.then_branch);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.check_stmt(else_branch);
+                }
+            }
+            Stmt::While(while_stmt) => {
+                self.type_of(&while_stmt.condition);
+                self.check_stmt(&while_stmt.body);
+            }
+            Stmt::DoWhile(do_while) => {
+                self.check_stmt(&do_while.body);
+                self.type_of(&do_while.condition);
+            }
+            Stmt::For(for_stmt) => {
+                self.begin_scope();
+                if let Some(initializer) = &for_stmt.initializer {
+                    self.check_stmt(initializer);
+                }
+                if let Some(condition) = &for_stmt.condition {
+                    self.type_of(condition);
+                }
+                if let Some(increment) = &for_stmt.increment {
+                    self.type_of(increment);
+                }
+                self.check_stmt(&for_stmt.body);
+                self.end_scope();
+            }
+            Stmt::Function(function) => {
+                let signature = self.signature(function, false);
+                let t = Type::Function(Some(Rc::new(signature)));
+                self.define(&function.name.lexeme, t);
+                self.check_function(function, None);
+            }
+            Stmt::Return(ret) => {
+                let t = match &ret.value {
+                    Some(value) => self.type_of(value),
+                    None => Type::Nil,
+                };
+                let Some((name, declared)) = self.functions.last() else {
+                    return;
+                };
+                if !self.assignable(&t, declared) {
+                    let message = format!(
+                        "Can't return {} from '{}', declared to return {}.",
+                        t, name, declared
+                    );
+                    my_error_token(ret.keyword.clone(), message);
+                }
+            }
+            Stmt::Class(class) => {
+                let name = &class.name.lexeme;
+                self.define(name, Type::Class(name.clone()));
+                if let Some(superclass) = &class.superclass {
+                    self.type_of(superclass);
+                }
+                for field in &class.fields {
+                    self.annotation(field.type_ann.as_ref(), true);
+                }
+                for method in &class.class_methods {
+                    self.check_function(method, None);
+                }
+                for method in &class.methods {
+                    self.check_function(method, Some(Type::Instance(name.clone())));
+                }
+            }
+            Stmt::Trait(trait_stmt) => {
+                let name = &trait_stmt.name.lexeme;
+                self.define(name, Type::Any);
+                for method in &trait_stmt.class_methods {
+                    self.check_function(method, None);
+                }
+                for method in &trait_stmt.methods {
+                    self.check_function(method, Some(Type::Instance(name.clone())));
+                }
+            }
+        }
+    }
+
+    // this_type 为 Some 时是实例方法
+    fn check_function(&mut self, function: &FunctionStmt, this_type: Option<Type>) {
+        let signature = self.signature(function, true);
+        self.begin_scope();
+        if let Some(this_type) = this_type {
+            self.define("this", this_type);
+        }
+        for (param, t) in function.params.iter().zip(&signature.params) {
+            if let Some(default) = &param.default {
+                let default_type = self.type_of(default);
+                if !self.assignable(&default_type, t) {
+                    let message = format!(
+                        "Type mismatch: '{}' is {} but the default is {}.",
+                        param.name.lexeme, t, default_type
+                    );
+                    my_error_token(param.name.clone(), message);
+                }
+            }
+            self.define(&param.name.lexeme, t.clone());
+        }
+        if let Some(rest) = &function.rest {
+            self.define(&rest.lexeme, Type::List);
+        }
+        self.functions.push((signature.name, signature.ret));
+        self.check_stmts(&function.fn_body);
+        self.functions.pop();
+        self.end_scope();
+    }
+
+    fn type_of(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal(literal) => match literal.value {
+                Scalar::Bool(_) => Type::Bool,
+                Scalar::Number(_) => Type::Number,
+                Scalar::String(_) => Type::String,
+                Scalar::Nil => Type::Nil,
+                _ => Type::Any,
+            },
+            Expr::Grouping(grouping) => self.type_of(&grouping.expression),
+            Expr::Variable(variable) => self.lookup(&variable.name.lexeme),
+            Expr::Assign(assign) => {
+                let t = self.type_of(&assign.value);
+                let declared = self.lookup(&assign.name.lexeme);
+                if !self.assignable(&t, &declared) {
+                    let message = format!(
+                        "Type mismatch: can't assign {} to '{}' of type {}.",
+                        t, assign.name.lexeme, declared
+                    );
+                    my_error_token(assign.name.clone(), message);
+                }
+                t
+            }
+            Expr::Unary(unary) => {
+                let t = self.type_of(&unary.right);
+                match unary.operator.t_type {
+                    TokenType::BANG => Type::Bool,
+                    _ if t.is_instance() => Type::Any,
+                    _ => {
+                        if !t.maybe_number() {
+                            let message = "Operand must be a number.".to_string();
+                            my_error_token(unary.operator.clone(), message);
+                        }
+                        Type::Number
+                    }
+                }
+            }
+            Expr::Binary(binary) => {
+                let left = self.type_of(&binary.left);
+                let right = self.type_of(&binary.right);
+                self.binary_type(&left, &right, &binary.operator)
+            }
+            Expr::Logical(logical) => {
+                let left = self.type_of(&logical.left);
+                let right = self.type_of(&logical.right);
+                match (&logical.operator.t_type, &left) {
+                    (TokenType::QuestionQuestion, Type::Nil) => right,
+                    _ if left == right => left,
+                    _ => Type::Any,
+                }
+            }
+            Expr::Call(call) => self.call_type(call),
+            Expr::Get(get) => {
+                let object = self.type_of(&get.object);
+                let t = self.member_type(&object, &get.name.lexeme);
+                if get.optional {
+                    Type::Any
+                } else {
+                    t
+                }
+            }
+            Expr::Set(set) => {
+                let object = self.type_of(&set.object);
+                let t = self.type_of(&set.value);
+                if let Type::Instance(class) = &object {
+                    let field =
+                        self.find_member(class, &|info| info.fields.get(&set.name.lexeme).cloned());
+                    if let Some(field) = field {
+                        if !self.assignable(&t, &field) {
+                            let message = format!(
+                                "Type mismatch: can't assign {} to field '{}' of type {}.",
+                                t, set.name.lexeme, field
+                            );
+                            my_error_token(set.name.clone(), message);
+                        }
+                    }
+                }
+                t
+            }
+            Expr::Index(index) => {
+                self.type_of(&index.object);
+                self.type_of(&index.index);
+                Type::Any
+            }
+            Expr::Spread(spread) => {
+                self.type_of(&spread.expression);
+                Type::Any
+            }
+            Expr::This(_) => self.lookup("this"),
+            Expr::Super(_) => Type::Any,
+        }
+    }
+
+    fn binary_type(&self, left: &Type, right: &Type, operator: &Token) -> Type {
+        use TokenType::*;
+        match operator.t_type {
+            EqualEqual | BangEqual | IS => Type::Bool,
+            // 实例可能重载了运算符，结果类型未知
+            _ if left.is_instance() || right.is_instance() => Type::Any,
+            PLUS => match (left, right) {
+                (Type::Number, Type::Number) => Type::Number,
+                (Type::String, Type::String) => Type::String,
+                (Type::Any, Type::Any) => Type::Any,
+                (Type::Any, t @ (Type::Number | Type::String))
+                | (t @ (Type::Number | Type::String), Type::Any) => t.clone(),
+                _ => {
+                    let message = "Operands must be two numbers or two strings.".to_string();
+                    my_error_token(operator.clone(), message);
+                    Type::Any
+                }
+            },
+            MINUS | STAR | SLASH | GREATER | GreaterEqual | LESS | LessEqual => {
+                if !left.maybe_number() || !right.maybe_number() {
+                    my_error_token(operator.clone(), "Operands must be numbers.".to_string());
+                }
+                match operator.t_type {
+                    MINUS | STAR | SLASH => Type::Number,
+                    _ => Type::Bool,
+                }
+            }
+            _ => Type::Any,
+        }
+    }
+
+    fn call_type(&mut self, call: &CallExpr) -> Type {
+        let callee = self.type_of(&call.callee);
+        let args: Vec<_> = call.arguments.iter().map(|arg| self.type_of(arg)).collect();
+        for arg in &call.named {
+            self.type_of(&arg.value);
+        }
+        let (signature, name, result) = match &callee {
+            Type::Function(Some(signature)) => {
+                let name = signature.name.clone();
+                (Some(signature.clone()), name, signature.ret.clone())
+            }
+            Type::Class(class) => {
+                let init = self.find_member(class, &|info| info.methods.get("init").cloned());
+                (init, class.clone(), Type::Instance(class.clone()))
+            }
+            _ => (None, String::new(), Type::Any),
+        };
+        // 有展开参数时位置对不上，交给运行时
+        let has_spread = call
+            .arguments
+            .iter()
+            .any(|arg| matches!(arg, Expr::Spread(_)));
+        if let (Some(signature), false) = (signature, has_spread) {
+            self.check_arguments(&name, &signature.params, &args, &call.parent);
+        }
+        if call.optional {
+            Type::Any
+        } else {
+            result
+        }
+    }
+
+    fn check_arguments(&self, name: &str, params: &[Type], args: &[Type], paren: &Token) {
+        for (i, (arg, param)) in args.iter().zip(params).enumerate() {
+            if !self.assignable(arg, param) {
+                let message = format!(
+                    "Argument {} of '{}' must be {} but got {}.",
+                    i + 1,
+                    name,
+                    param,
+                    arg
+                );
+                my_error_token(paren.clone(), message);
+            }
+        }
+    }
+
+    fn member_type(&self, object: &Type, name: &str) -> Type {
+        match object {
+            Type::Instance(class) => {
+                let field = self.find_member(class, &|info| info.fields.get(name).cloned());
+                let getter = || self.find_member(class, &|info| info.getters.get(name).cloned());
+                let method = || {
+                    self.find_member(class, &|info| info.methods.get(name).cloned())
+                        .map(|signature| Type::Function(Some(signature)))
+                };
+                field.or_else(getter).or_else(method).unwrap_or(Type::Any)
+            }
+            Type::Class(class) => self
+                .find_member(class, &|info| info.class_methods.get(name).cloned())
+                .map_or(Type::Any, |signature| Type::Function(Some(signature))),
+            _ => Type::Any,
+        }
+    }
+}
@@ -0,0 +1,298 @@
+mod impls;
+mod types;
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    error::my_error_token,
+    stmt::{function::FunctionStmt, Stmt},
+    token::Token,
+};
+pub use types::*;
+
+// `check` 子命令：只检查有标注的地方，其余都当作 Any，求值器不看标注
+pub struct Checker {
+    scopes: Vec<HashMap<String, Type>>,
+    classes: HashMap<String, ClassInfo>,
+    // 当前函数的名字和声明的返回类型
+    functions: Vec<(String, Type)>,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        let native = |name: &str, ret: Type| {
+            let signature = Signature {
+                name: name.to_string(),
+                params: vec![],
+                ret,
+            };
+            (name.to_string(), Type::Function(Some(Rc::new(signature))))
+        };
+        let globals = [
+            native("clock", Type::Number),
+            native("log", Type::Nil),
+            native("typeOf", Type::String),
+            native("fields", Type::List),
+            native("methods", Type::List),
+            native("superclass", Type::Any),
+            native("str", Type::String),
+        ];
+        Self {
+            scopes: vec![globals.into_iter().collect()],
+            classes: HashMap::new(),
+            functions: vec![],
+        }
+    }
+
+    pub fn check(&mut self, stmts: &[Stmt]) {
+        let mut names = vec![];
+        collect_classes(stmts, &mut names);
+        for (name, _) in &names {
+            self.classes
+                .insert(name.lexeme.clone(), ClassInfo::default());
+        }
+        for (_, stmt) in &names {
+            self.add_class_info(stmt);
+        }
+        self.check_stmts(stmts);
+    }
+
+    fn add_class_info(&mut self, stmt: &Stmt) {
+        let mut info = ClassInfo::default();
+        let resolve = |function: &FunctionStmt| self.signature(function, false);
+        let name = match stmt {
+            Stmt::Class(class) => {
+                info.superclass = class.superclass_name();
+                info.traits = class
+                    .traits
+                    .iter()
+                    .filter_map(|t| t.to_variable().map(|v| v.name.lexeme.clone()))
+                    .collect();
+                for field in &class.fields {
+                    let t = self.annotation(field.type_ann.as_ref(), false);
+                    info.fields.insert(field.name.lexeme.clone(), t);
+                }
+                info.add_methods(&class.methods, &class.class_methods, resolve);
+                &class.name
+            }
+            Stmt::Trait(trait_stmt) => {
+                info.add_methods(&trait_stmt.methods, &trait_stmt.class_methods, resolve);
+                &trait_stmt.name
+            }
+            _ => return,
+        };
+        self.classes.insert(name.lexeme.clone(), info);
+    }
+
+    // 把标注解析成类型，没有标注就是 Any；report 为 false 时不报告未知类型（预扫描用）
+    fn annotation(&self, type_ann: Option<&Token>, report: bool) -> Type {
+        let Some(token) = type_ann else {
+            return Type::Any;
+        };
+        match token.lexeme.as_str() {
+            "Any" => Type::Any,
+            "Number" => Type::Number,
+            "String" => Type::String,
+            "Bool" => Type::Bool,
+            "Nil" => Type::Nil,
+            "List" => Type::List,
+            "Function" => Type::Function(None),
+            name if self.classes.contains_key(name) => Type::Instance(name.to_string()),
+            name => {
+                if report {
+                    my_error_token(token.clone(), format!("Unknown type '{}'.", name));
+                }
+                Type::Any
+            }
+        }
+    }
+
+    fn signature(&self, function: &FunctionStmt, report: bool) -> Signature {
+        Signature {
+            name: function.name.lexeme.clone(),
+            params: function
+                .params
+                .iter()
+                .map(|p| self.annotation(p.type_ann.as_ref(), report))
+                .collect(),
+            ret: self.annotation(function.return_type.as_ref(), report),
+        }
+    }
+
+    // 渐进式类型：任何一边是 Any 都放行；nil 可以赋给对象类型
+    fn assignable(&self, from: &Type, to: &Type) -> bool {
+        match (from, to) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Nil, Type::Instance(_) | Type::Class(_) | Type::Function(_) | Type::List) => {
+                true
+            }
+            (Type::Function(_), Type::Function(_)) => true,
+            (Type::Instance(from), Type::Instance(to)) => self.is_subtype(from, to),
+            _ => from == to,
+        }
+    }
+
+    // 子类，或者自己/祖先混入了这个 trait
+    fn is_subtype(&self, class: &str, target: &str) -> bool {
+        if class == target {
+            return true;
+        }
+        let Some(info) = self.classes.get(class) else {
+            return false;
+        };
+        info.traits.iter().any(|t| t == target)
+            || info
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| self.is_subtype(superclass, target))
+    }
+
+    // 沿着超类和 trait 查找成员
+    fn find_member<T>(&self, class: &str, find: &impl Fn(&ClassInfo) -> Option<T>) -> Option<T> {
+        let info = self.classes.get(class)?;
+        if let Some(found) = find(info) {
+            return Some(found);
+        }
+        for name in &info.traits {
+            if let Some(found) = self.classes.get(name).and_then(find) {
+                return Some(found);
+            }
+        }
+        self.find_member(info.superclass.as_ref()?, find)
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+    fn define(&mut self, name: &str, t: Type) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), t);
+    }
+    fn lookup(&self, name: &str) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+            .unwrap_or(Type::Any)
+    }
+}
+
+// 所有 class/trait 声明，包括函数和块里面的
+fn collect_classes<'a>(stmts: &'a [Stmt], out: &mut Vec<(&'a Token, &'a Stmt)>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Class(class) => {
+                out.push((&class.name, stmt));
+                for method in class.methods.iter().chain(&class.class_methods) {
+                    collect_classes(&method.fn_body, out);
+                }
+            }
+            Stmt::Trait(trait_stmt) => {
+                out.push((&trait_stmt.name, stmt));
+                for method in trait_stmt.methods.iter().chain(&trait_stmt.class_methods) {
+                    collect_classes(&method.fn_body, out);
+                }
+            }
+            Stmt::Function(function) => collect_classes(&function.fn_body, out),
+            Stmt::Block(block) => collect_classes(&block.statements, out),
+            Stmt::If(if_stmt) => {
+                collect_classes(std::slice::from_ref(&if_stmt.then_branch), out);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    collect_classes(std::slice::from_ref(else_branch), out);
+                }
+            }
+            Stmt::While(while_stmt) => collect_classes(std::slice::from_ref(&while_stmt.body), out),
+            Stmt::DoWhile(do_while) => collect_classes(std::slice::from_ref(&do_while.body), out),
+            Stmt::For(for_stmt) => collect_classes(std::slice::from_ref(&for_stmt.body), out),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+fn check_source(src: &str) -> (bool, Vec<crate::error::Report>) {
+    use crate::{error::capture_reports, test_util::parse_source};
+
+    // 返回值是 HAD_ERROR，main 里据此以 65 退出
+    capture_reports(|| {
+        Checker::new().check(&parse_source(src));
+        crate::error::had_error()
+    })
+}
+
+#[test]
+fn accepts_well_typed_and_unannotated_code() {
+    let src = r#"
+class Point {
+  var x: Number;
+  init(x: Number) { this.x = x; }
+  norm(): Number { return this.x * this.x; }
+}
+class Point3 < Point {}
+var p: Point = Point3(1);
+var q: Point = nil;
+fun greet(name: String): String { return "hi " + name; }
+var s: String = greet("bob");
+var n: Number = p.norm() + 1;
+var loose = "a";
+loose = 1;
+var any: Any = loose - 1;
+"#;
+    let (had_error, reports) = check_source(src);
+    assert!(!had_error);
+    assert!(reports.is_empty(), "{:?}", crate::test_util::report_lines(&reports));
+}
+
+#[test]
+fn rejects_mismatched_annotations() {
+    let src = r#"
+var n: Number = "one";
+fun f(a: String): Bool { return a; }
+f(1);
+class Point { var x: Number; }
+Point().x = "x";
+var s: String = "s";
+s = 2;
+var m: Number = s = "t";
+var u: Widget = nil;
+print "a" - 1;
+"#;
+    let (had_error, reports) = check_source(src);
+    assert!(had_error);
+    assert!(reports.iter().all(|report| !report.warning));
+    assert_eq!(
+        crate::test_util::report_lines(&reports),
+        [
+            "[line 2] Type mismatch: 'n' is Number but the initializer is String.",
+            "[line 3] Can't return String from 'f', declared to return Bool.",
+            "[line 4] Argument 1 of 'f' must be String but got Number.",
+            "[line 6] Type mismatch: can't assign String to field 'x' of type Number.",
+            "[line 8] Type mismatch: can't assign Number to 's' of type String.",
+            // 赋值表达式的类型是右边的值
+            "[line 9] Type mismatch: 'm' is Number but the initializer is String.",
+            "[line 10] Unknown type 'Widget'.",
+            "[line 11] Operands must be numbers.",
+        ]
+    );
+}
+
+#[test]
+fn evaluator_ignores_annotations() {
+    use crate::{
+        data_types::scaler::Scalar,
+        test_util::{global, run_source},
+    };
+
+    let src = "fun f(a: Number): String { return a; } var x: String = f(1);";
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "x"), Scalar::Number(1.0));
+}
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, fmt::Display, rc::Rc};
+
+use crate::stmt::function::FunctionStmt;
+
+// check 用的静态类型，没有标注的值都是 Any，和 Any 相关的检查一律放行
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Any,
+    Number,
+    String,
+    Bool,
+    Nil,
+    List,
+    // None：只知道是函数，不知道签名
+    Function(Option<Rc<Signature>>),
+    // 类对象本身，调用得到 Instance
+    Class(String),
+    // 类或 trait 的实例
+    Instance(String),
+}
+impl Type {
+    pub fn is_any(&self) -> bool {
+        matches!(self, Type::Any)
+    }
+    pub fn is_instance(&self) -> bool {
+        matches!(self, Type::Instance(_))
+    }
+    // 运行时可能是数字：Any 也算
+    pub fn maybe_number(&self) -> bool {
+        matches!(self, Type::Any | Type::Number)
+    }
+}
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Any => write!(f, "Any"),
+            Type::Number => write!(f, "Number"),
+            Type::String => write!(f, "String"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Nil => write!(f, "Nil"),
+            Type::List => write!(f, "List"),
+            Type::Function(_) => write!(f, "Function"),
+            Type::Class(name) => write!(f, "class {}", name),
+            Type::Instance(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub params: Vec<Type>,
+    pub ret: Type,
+}
+
+// 预先收集的类和 trait 信息，方法体里可能用到后面才声明的类
+#[derive(Debug, Default)]
+pub struct ClassInfo {
+    pub superclass: Option<String>,
+    pub traits: Vec<String>,
+    pub fields: HashMap<String, Type>,
+    pub methods: HashMap<String, Rc<Signature>>,
+    pub getters: HashMap<String, Type>,
+    pub class_methods: HashMap<String, Rc<Signature>>,
+}
+impl ClassInfo {
+    pub fn add_methods<'a>(
+        &mut self,
+        methods: impl IntoIterator<Item = &'a Rc<FunctionStmt>>,
+        class_methods: impl IntoIterator<Item = &'a Rc<FunctionStmt>>,
+        resolve: impl Fn(&FunctionStmt) -> Signature,
+    ) {
+        for method in methods {
+            let signature = resolve(method);
+            if method.getter {
+                self.getters
+                    .insert(method.name.lexeme.clone(), signature.ret);
+            } else {
+                self.methods
+                    .insert(method.name.lexeme.clone(), Rc::new(signature));
+            }
+        }
+        for method in class_methods {
+            self.class_methods
+                .insert(method.name.lexeme.clone(), Rc::new(resolve(method)));
+        }
+    }
+}
@@ -1,11 +1,28 @@
 use std::path::PathBuf;
 
+use clap::builder::RangedU64ValueParser;
+
+use crate::{
+    constants::{DEFAULT_MAX_CALL_DEPTH, MAX_CALL_DEPTH},
+    linter::Rule,
+};
+
 #[derive(clap::Parser)]
 pub struct ArgsParser {
     #[command(subcommand)]
     pub cmds: Cmd,
     #[arg(short, long,)]
     pub resolver: Option<bool>,
+    /// Fold constants and drop dead branches before running
+    #[arg(short = 'O', long)]
+    pub optimize: bool,
+    /// Maximum depth of nested function calls before a "Stack overflow." error
+    #[arg(
+        long,
+        default_value_t = DEFAULT_MAX_CALL_DEPTH,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..=MAX_CALL_DEPTH as u64),
+    )]
+    pub max_depth: usize,
 }
 
 #[derive(clap::Subcommand)]
@@ -15,4 +32,48 @@ pub enum Cmd {
     Parse { file: PathBuf },
     Evaluate  { file: PathBuf },
     Run  { file: PathBuf },
+    /// Print the parsed program in a machine-readable form
+    Ast {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = AstFormat::Json)]
+        format: AstFormat,
+    },
+    /// Run a program from an AST previously written by `ast --format json`
+    RunAst { file: PathBuf },
+    /// Type-check annotated code without running it
+    Check { file: PathBuf },
+    /// Report suspicious code; rules come from `--config` and `--enable`/`--disable`
+    Lint {
+        file: PathBuf,
+        /// JSON object mapping rule names to true/false
+        #[arg(long)]
+        config: Option<PathBuf>,
+        #[arg(long, value_enum)]
+        enable: Vec<Rule>,
+        #[arg(long, value_enum)]
+        disable: Vec<Rule>,
+        #[arg(long, value_enum, default_value_t = LintFormat::Human)]
+        format: LintFormat,
+    },
+    /// Rewrite files in the canonical source style
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Only report files that are not formatted, exit non-zero if any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Serve the Language Server Protocol over stdin/stdout
+    Lsp,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum AstFormat {
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum LintFormat {
+    Human,
+    Json,
 }
@@ -4,25 +4,34 @@ use lazy_static::lazy_static;
 
 use crate::token_type::TokenType;
 
+// 默认的函数调用嵌套层数上限，超过时报 "Stack overflow."
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+// `--max-depth` 允许的最大值，解释器线程的栈按它分配，太大了线程起不来
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
 lazy_static! {
     pub static ref keywords_map: HashMap<String, TokenType> = {
         [
             ("and", TokenType::AND),
             ("class", TokenType::CLASS),
+            ("do", TokenType::DO),
             ("else", TokenType::ELSE),
             ("false", TokenType::FALSE),
             ("for", TokenType::FOR),
             ("fun", TokenType::FUN),
             ("if", TokenType::IF),
+            ("is", TokenType::IS),
             ("nil", TokenType::NIL),
             ("or", TokenType::OR),
             ("print", TokenType::PRINT),
             ("return", TokenType::RETURN),
             ("super", TokenType::SUPER),
             ("this", TokenType::THIS),
+            ("trait", TokenType::TRAIT),
             ("true", TokenType::TRUE),
             ("var", TokenType::VAR),
             ("while", TokenType::WHILE),
+            ("with", TokenType::WITH),
         ]
         .iter()
         .map(|x| (x.0.to_string(), x.1.clone()))
@@ -1,29 +1,39 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
-    callable::Callable,
-    evaluator::{Evaluator, InterpretResult},
+    callable::{Arity, Callable},
+    error::report_runtime,
+    evaluator::{Evaluator, InterpretError, InterpretResult},
+    token::Token,
+    InterpretRtErr,
 };
 
-use super::{InstanceValue, Scalar, UserFn};
+use super::{InstanceValue, Scalar, TraitValue, UserFn};
 
-#[derive(Clone, Debug)]
+// 已经混入的方法来自哪个 trait，key 是 (是否静态方法, 方法名)
+pub type MixedIn = HashMap<(bool, String), String>;
+
+// 类对象只有一份，Scalar::Class、实例和子类都通过 Rc 指向它
+#[derive(Debug)]
 pub struct ClassValue {
     pub name: String,
     pub methods: HashMap<String, UserFn>,
+    pub class_methods: HashMap<String, UserFn>,
     pub super_class: Option<Rc<ClassValue>>,
-}
-impl PartialEq for ClassValue {
-    fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
-    }
+    // 混入过的 trait，`x is Printable` 要用
+    pub traits: Vec<Rc<TraitValue>>,
+    // `A.count = 1` 设置的类字段，所有指向这个类的引用都能看到
+    fields: RefCell<HashMap<String, Scalar>>,
 }
 impl ClassValue {
     pub fn new(name: &str, super_class: Option<Rc<ClassValue>>) -> Self {
         Self {
             name: name.to_string(),
             methods: HashMap::new(),
+            class_methods: HashMap::new(),
             super_class,
+            traits: vec![],
+            fields: RefCell::default(),
         }
     }
     pub fn find_method(&self, name: &str) -> Option<UserFn> {
@@ -40,6 +50,78 @@ impl ClassValue {
     pub fn add_method(&mut self, name: &str, method: UserFn) {
         self.methods.insert(name.to_string(), method);
     }
+    pub fn find_class_method(&self, name: &str) -> Option<UserFn> {
+        if let Some(method) = self.class_methods.get(name) {
+            return Some(method.clone());
+        }
+        self.super_class
+            .as_ref()
+            .and_then(|super_class| super_class.find_class_method(name))
+    }
+    pub fn get_field(&self, name: &str) -> Option<Scalar> {
+        self.fields.borrow().get(name).cloned()
+    }
+    pub fn set_field(&self, name: &str, value: Scalar) {
+        self.fields.borrow_mut().insert(name.to_string(), value);
+    }
+    pub fn add_class_method(&mut self, name: &str, method: UserFn) {
+        self.class_methods.insert(name.to_string(), method);
+    }
+    // 类自己的方法 > trait > 超类；两个 trait 有同名方法而类没有覆盖时返回冲突信息
+    pub fn mix_in(&mut self, tr: &Rc<TraitValue>, mixed: &mut MixedIn) -> Result<(), String> {
+        let tables = [
+            (false, &mut self.methods, &tr.methods),
+            (true, &mut self.class_methods, &tr.class_methods),
+        ];
+        for (is_static, table, methods) in tables {
+            let mut names: Vec<_> = methods.keys().collect();
+            names.sort();
+            for name in names {
+                let key = (is_static, name.clone());
+                if let Some(other) = mixed.get(&key) {
+                    return Err(format!(
+                        "Method '{}' is defined by both trait {} and trait {}.",
+                        name, other, tr.name
+                    ));
+                }
+                if table.contains_key(name) {
+                    continue;
+                }
+                let method = methods[name].with_super(self.super_class.clone());
+                table.insert(name.clone(), method);
+                mixed.insert(key, tr.name.clone());
+            }
+        }
+        self.traits.push(tr.clone());
+        Ok(())
+    }
+    // 自己或者某个祖先类是 `class`
+    pub fn is_subclass_of(&self, class: &ClassValue) -> bool {
+        if std::ptr::eq(self, class) {
+            return true;
+        }
+        self.super_class
+            .as_ref()
+            .is_some_and(|super_class| super_class.is_subclass_of(class))
+    }
+    // 自己或者某个祖先类混入了 `tr`
+    pub fn has_trait(&self, tr: &Rc<TraitValue>) -> bool {
+        self.traits.iter().any(|t| Rc::ptr_eq(t, tr))
+            || self
+                .super_class
+                .as_ref()
+                .is_some_and(|super_class| super_class.has_trait(tr))
+    }
+    // 包括继承来的方法，按名字排序
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.methods.keys().cloned().collect();
+        if let Some(super_class) = &self.super_class {
+            names.extend(super_class.method_names());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
     pub fn bind_init(&self, instance_value: &Scalar) -> Option<UserFn> {
         if let Some(method) = self.find_method("init") {
             let a = method.bind(instance_value.clone());
@@ -48,21 +130,22 @@ impl ClassValue {
         return None;
     }
 }
-impl Callable for ClassValue {
+// 构造实例要拿到类的 Rc，所以实现在 Rc<ClassValue> 上
+impl Callable for Rc<ClassValue> {
     fn to_string(&self) -> String {
         format!("<class {}>", self.name)
     }
 
-    fn arity(&self) -> usize {
-        if let Some(method) = self.methods.get("init") {
+    fn arity(&self) -> Arity {
+        if let Some(method) = self.find_method("init") {
             method.arity()
         } else {
-            0
+            Arity::exact(0)
         }
     }
 
     fn call(&self, evaluator: &mut Evaluator, args: Vec<Scalar>) -> InterpretResult<Scalar> {
-        let instance = InstanceValue::new(self.clone());
+        let instance = InstanceValue::new(Rc::clone(self));
         let instance_value: Scalar = instance.into();
         if let Some(method) = self.bind_init(&instance_value) {
             method.call(evaluator, args)?;
@@ -70,4 +153,112 @@ impl Callable for ClassValue {
         }
         Ok(instance_value)
     }
+
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Scalar>,
+        named: Vec<(Token, Scalar)>,
+        paren: &Token,
+    ) -> InterpretResult<Scalar> {
+        let instance_value: Scalar = InstanceValue::new(Rc::clone(self)).into();
+        let Some(method) = self.bind_init(&instance_value) else {
+            report_runtime(paren.line, format!("Unknown argument '{}'.", named[0].0.lexeme));
+            return InterpretRtErr!(;"unknown argument");
+        };
+        method.call_named(evaluator, args, named, paren)?;
+        Ok(instance_value)
+    }
+}
+
+#[test]
+fn trait_methods_use_super_of_the_host_class() {
+    use crate::test_util::{global, report_lines, resolve_source, run_source};
+
+    let src = r#"
+class Base { greet() { return "base"; } }
+class Other { greet() { return "other"; } }
+trait Polite { greet() { return "please, " + super.greet(); } }
+class D < Base with Polite {}
+class E < Other with Polite {}
+var d = D().greet();
+var e = E().greet();
+"#;
+    assert!(report_lines(&resolve_source(src)).is_empty());
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(
+        global(&evaluator, "d"),
+        Scalar::String("please, base".into())
+    );
+    assert_eq!(
+        global(&evaluator, "e"),
+        Scalar::String("please, other".into())
+    );
+
+    // 混进没有超类的类，调用时才报错
+    let src = "trait T { hi() { return super.hi(); } } class C with T {} C().hi();";
+    let (_, result) = run_source(src);
+    assert!(result.is_err());
+}
+
+#[test]
+fn static_methods_and_getters() {
+    use crate::test_util::{global, run_source, runtime_errors};
+
+    let src = r#"
+class Shape {
+  class named(n) { return "shape " + n; }
+  area { return this.w * this.h; }
+}
+class Square < Shape { init(w) { this.w = w; this.h = w; } }
+var label = Square.named("sq");
+var area = Square(3).area;
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    // 类方法跟着 `<` 继承，getter 访问时不用加括号
+    assert_eq!(global(&evaluator, "label"), Scalar::String("shape sq".into()));
+    assert_eq!(global(&evaluator, "area"), Scalar::Number(9.0));
+
+    // 实例上找不到类方法
+    assert_eq!(
+        runtime_errors("class A { class make() { return 1; } }\nA().make;"),
+        ["[line 2] Field make not found"]
+    );
+
+    let src = "class A { area { return nil + 1; } }\nfun f(a) { return a.area; }\nf(A());";
+    let (mut evaluator, result) = run_source(src);
+    assert!(result.is_err());
+    let frames: Vec<_> = evaluator
+        .take_backtrace()
+        .iter()
+        .map(|frame| frame.to_string())
+        .collect();
+    assert_eq!(frames, ["[line 3] f()", "[line 2] A.area()"]);
+}
+
+#[test]
+fn class_aliases_share_one_class() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+class A { class bump() { A.count = A.count + 1; return A.count; } }
+class B < A {}
+var A2 = A;
+var same = A2 == A;
+var parent = superclass(B) == A;
+var is_a = B() is A;
+A.count = 0;
+A2.bump();
+A.bump();
+var count = A2.count;
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "same"), Scalar::Bool(true));
+    assert_eq!(global(&evaluator, "parent"), Scalar::Bool(true));
+    assert_eq!(global(&evaluator, "is_a"), Scalar::Bool(true));
+    // 类字段存在唯一的类对象上，通过别名修改也能看到
+    assert_eq!(global(&evaluator, "count"), Scalar::Number(2.0));
 }
@@ -4,8 +4,9 @@ pub use native_function::*;
 pub use user_function::*;
 
 use crate::{
-    callable::Callable,
+    callable::{Arity, Callable},
     evaluator::{Evaluator, InterpretResult},
+    token::Token,
 };
 
 use super::Scalar;
@@ -32,6 +33,18 @@ impl Callable for FunctionValue {
             FunctionValue::Native(native_fn) => native_fn.call(evaluator, args),
         }
     }
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Scalar>,
+        named: Vec<(Token, Scalar)>,
+        paren: &Token,
+    ) -> InterpretResult<Scalar> {
+        match self {
+            FunctionValue::User(user_fn) => user_fn.call_named(evaluator, args, named, paren),
+            FunctionValue::Native(native_fn) => native_fn.call_named(evaluator, args, named, paren),
+        }
+    }
     fn to_string(&self) -> String {
         match self {
             FunctionValue::User(user_fn) => user_fn.to_string(),
@@ -39,10 +52,19 @@ impl Callable for FunctionValue {
         }
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         match self {
             FunctionValue::User(user_fn) => user_fn.arity(),
             FunctionValue::Native(native_fn) => native_fn.arity(),
         }
     }
 }
+impl PartialEq for FunctionValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FunctionValue::User(l0), FunctionValue::User(r0)) => l0.same_function(r0),
+            (FunctionValue::Native(l0), FunctionValue::Native(r0)) => l0 == r0,
+            _ => false,
+        }
+    }
+}
@@ -1,18 +1,25 @@
 use std::time::SystemTime;
 
 use crate::{
-    callable::Callable,
+    callable::{Arity, Callable},
     data_types::scaler::Scalar,
-    evaluator::{Evaluator, InterpretResult},
+    evaluator::{Evaluator, InterpretError, InterpretResult},
+    InterpretRtErr,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 
 pub enum NativeFn {
     Clock,
     Log,
+    TypeOf,
+    Fields,
+    Methods,
+    Superclass,
+    Str,
 }
 
+// native 只返回错误信息，行号由调用处补上
 impl Callable for NativeFn {
     fn call(&self, evaluator: &mut Evaluator, args: Vec<Scalar>) -> InterpretResult<Scalar> {
         let duration = SystemTime::now()
@@ -21,11 +28,110 @@ impl Callable for NativeFn {
         let value = match self {
             NativeFn::Clock => Scalar::Number(duration.as_secs_f64()),
             NativeFn::Log => Scalar::Nil,
+            NativeFn::Str => evaluator
+                .stringify(&args[0])
+                .map_err(already_reported)?
+                .into(),
+            NativeFn::TypeOf => args[0].type_name().into(),
+            NativeFn::Fields => {
+                let Some(instance) = args[0].as_instance() else {
+                    return InterpretRtErr!(;"Only instances have fields.");
+                };
+                let mut names: Vec<_> = instance.borrow().fields.keys().cloned().collect();
+                names.sort();
+                names.into_iter().map(Scalar::String).collect::<Vec<_>>().into()
+            }
+            NativeFn::Methods => {
+                let Some(class) = args[0].as_class() else {
+                    return InterpretRtErr!(;"Only classes have methods.");
+                };
+                let names = class.method_names().into_iter().map(Scalar::String);
+                names.collect::<Vec<_>>().into()
+            }
+            NativeFn::Superclass => {
+                let Some(class) = args[0].as_class() else {
+                    return InterpretRtErr!(;"Only classes have a superclass.");
+                };
+                match &class.super_class {
+                    Some(super_class) => super_class.clone().into(),
+                    None => Scalar::Nil,
+                }
+            }
         };
         Ok(value)
     }
 
-    fn arity(&self) -> usize {
-        0
+    fn arity(&self) -> Arity {
+        match self {
+            NativeFn::Clock | NativeFn::Log => Arity::exact(0),
+            NativeFn::TypeOf
+            | NativeFn::Fields
+            | NativeFn::Methods
+            | NativeFn::Superclass
+            | NativeFn::Str => Arity::exact(1),
+        }
     }
 }
+
+// toString() 里的错误在方法内部已经报告过了
+fn already_reported(e: InterpretError) -> InterpretError {
+    match e {
+        InterpretError::Runtime(_) => InterpretError::Other,
+        e => e,
+    }
+}
+
+#[test]
+fn is_and_reflection_natives() {
+    use crate::test_util::{global, run_source, runtime_errors};
+
+    let src = r#"
+trait Named { name() { return "p"; } }
+class Base { init() { this.y = 2; this.x = 1; } area() { return 0; } }
+class Point < Base with Named {}
+var p = Point();
+var is_point = p is Point;
+var is_base = p is Base;
+var is_named = p is Named;
+var base_is_point = Base() is Point;
+var number_is_base = 1 is Base;
+var types = typeOf(1) + typeOf("s") + typeOf(nil) + typeOf(true)
+  + typeOf(p) + typeOf(Point) + typeOf(Named) + typeOf(clock) + typeOf(fields(p));
+var field_names = fields(p);
+var method_names = methods(Point);
+var parent = superclass(Point);
+var root = superclass(Base);
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    let value = |name: &str| global(&evaluator, name);
+    let strings = |name: &str| {
+        let list = value(name).as_list().unwrap();
+        let items = list.borrow();
+        items.iter().map(|item| item.to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(value("is_point"), Scalar::Bool(true));
+    assert_eq!(value("is_base"), Scalar::Bool(true));
+    assert_eq!(value("is_named"), Scalar::Bool(true));
+    assert_eq!(value("base_is_point"), Scalar::Bool(false));
+    assert_eq!(value("number_is_base"), Scalar::Bool(false));
+    assert_eq!(
+        value("types"),
+        Scalar::String("numberstringnilboolinstanceclasstraitfunctionlist".into())
+    );
+    assert_eq!(strings("field_names"), ["x", "y"]);
+    assert_eq!(strings("method_names"), ["area", "init", "name"]);
+    assert!(matches!(value("parent"), Scalar::Class(class) if class.name == "Base"));
+    assert_eq!(value("root"), Scalar::Nil);
+
+    assert_eq!(
+        runtime_errors("class A {}\nA() is 1;"),
+        ["[line 2] Right operand of 'is' must be a class or trait."]
+    );
+    assert_eq!(runtime_errors("fields(1);"), ["[line 1] Only instances have fields."]);
+    assert_eq!(runtime_errors("methods(1);"), ["[line 1] Only classes have methods."]);
+    assert_eq!(
+        runtime_errors("superclass(nil);"),
+        ["[line 1] Only classes have a superclass."]
+    );
+}
@@ -1,11 +1,14 @@
 use std::{cell::RefCell, rc::Rc};
 
 use crate::{
-    callable::Callable,
-    data_types::scaler::{InstanceValue, Scalar},
+    callable::{Arity, Callable},
+    data_types::scaler::{ClassValue, InstanceValue, Scalar},
     environment::{Environment, EnvironmentType},
+    error::report_runtime,
     evaluator::{Evaluator, InterpretError, InterpretResult},
     stmt::function::FunctionStmt,
+    token::Token,
+    InterpretRtErr,
 };
 
 #[derive(Debug, Clone)]
@@ -14,6 +17,8 @@ pub struct UserFn {
     pub instance: Option<Rc<RefCell<Scalar>>>, // 是否 bind 过
     closure: EnvironmentType,
     declaration: Rc<FunctionStmt>,
+    // 声明这个方法的类或 trait，调用栈里显示用；普通函数为 None
+    owner: Option<Rc<str>>,
 }
 impl UserFn {
     pub fn new(env: EnvironmentType, delc: Rc<FunctionStmt>) -> Self {
@@ -21,6 +26,13 @@ impl UserFn {
             closure: env,
             declaration: delc,
             instance: None,
+            owner: None,
+        }
+    }
+    pub fn method(env: EnvironmentType, delc: Rc<FunctionStmt>, owner: &str) -> Self {
+        Self {
+            owner: Some(owner.into()),
+            ..Self::new(env, delc)
         }
     }
     pub fn bind(&self, instance: Scalar) -> Self {
@@ -30,29 +42,135 @@ impl UserFn {
             closure: env,
             declaration: self.declaration.clone(),
             instance: Some(Rc::new(RefCell::new(instance))),
+            owner: self.owner.clone(),
+        }
+    }
+    // trait 方法混入类时在闭包外包一层放 super，类没有超类时 super 是 nil
+    pub fn with_super(&self, super_class: Option<Rc<ClassValue>>) -> Self {
+        let env = Environment::new(Some(self.closure.clone()), Some("super env"));
+        let value = super_class.map_or(Scalar::Nil, Into::into);
+        env.borrow_mut().define("super", Some(value));
+        Self {
+            closure: env,
+            ..self.clone()
         }
     }
     pub fn is_init(&self) -> bool {
         self.declaration.name.lexeme == "init"
     }
-}
-impl Callable for UserFn {
-    fn to_string(&self) -> String {
-        let name = &self.declaration.name.lexeme;
-        format!("<fn {}>", name)
+    pub fn is_getter(&self) -> bool {
+        self.declaration.getter
     }
-
-    fn call(&self, evaluator: &mut Evaluator, args: Vec<Scalar>) -> InterpretResult<Scalar> {
+    // 同一个声明、同一个闭包；bind 出来的方法每次闭包都是新的，改为比较绑定的 this
+    pub fn same_function(&self, other: &Self) -> bool {
+        if !Rc::ptr_eq(&self.declaration, &other.declaration) {
+            return false;
+        }
+        match (&self.instance, &other.instance) {
+            (Some(this), Some(other_this)) => *this.borrow() == *other_this.borrow(),
+            (None, None) => Rc::ptr_eq(&self.closure, &other.closure),
+            _ => false,
+        }
+    }
+    // 声明所在的行
+    pub(crate) fn line(&self) -> usize {
+        self.declaration.name.line
+    }
+    pub(crate) fn declaration_ptr(&self) -> *const FunctionStmt {
+        Rc::as_ptr(&self.declaration)
+    }
+    // 位置参数按 params 放进 slots，多出来的给 rest
+    fn split_args(&self, args: Vec<Scalar>) -> (Vec<Option<Scalar>>, Vec<Scalar>) {
+        let count = self.declaration.params.len();
+        let mut slots: Vec<_> = args.into_iter().map(Some).collect();
+        if slots.len() > count {
+            let rest = slots.split_off(count).into_iter().flatten().collect();
+            (slots, rest)
+        } else {
+            slots.resize(count, None);
+            (slots, vec![])
+        }
+    }
+    // 检查参数后执行函数体；函数体里的尾调用原样返回，由 `finish_tail_calls` 接着跑
+    pub(crate) fn enter(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Scalar>,
+        named: Vec<(Token, Scalar)>,
+        paren: &Token,
+    ) -> InterpretResult<Scalar> {
+        // 具名参数可以补上缺的位置参数，这时只检查上限
+        let arity = self.arity();
+        let count_ok = if named.is_empty() {
+            arity.accepts(args.len())
+        } else {
+            arity.max.map_or(true, |max| args.len() <= max)
+        };
+        if !count_ok {
+            let message = format!("Expected {} arguments but got {}.", arity, args.len());
+            report_runtime(paren.line, message);
+            return InterpretRtErr!(;"wrong number of arguments");
+        }
+        if named.is_empty() {
+            let (slots, rest) = self.split_args(args);
+            return self.invoke(evaluator, slots, rest);
+        }
+        let params = &self.declaration.params;
+        let (mut slots, rest) = self.split_args(args);
+        for (name, value) in named {
+            let Some(idx) = params.iter().position(|p| p.name.lexeme == name.lexeme) else {
+                report_runtime(paren.line, format!("Unknown argument '{}'.", name.lexeme));
+                return InterpretRtErr!(;"unknown argument");
+            };
+            if slots[idx].is_some() {
+                report_runtime(paren.line, format!("Duplicate argument '{}'.", name.lexeme));
+                return InterpretRtErr!(;"duplicate argument");
+            }
+            slots[idx] = Some(value);
+        }
+        for (param, slot) in params.iter().zip(&slots) {
+            if slot.is_none() && param.default.is_none() {
+                report_runtime(paren.line, format!("Missing argument '{}'.", param.name.lexeme));
+                return InterpretRtErr!(;"missing argument");
+            }
+        }
+        self.invoke(evaluator, slots, rest)
+    }
+    // slots 与 params 一一对应，None 的位置用默认值
+    fn bind_params(
+        &self,
+        evaluator: &mut Evaluator,
+        slots: Vec<Option<Scalar>>,
+        rest: Vec<Scalar>,
+    ) -> InterpretResult<EnvironmentType> {
         let env = Environment::new(Some(self.closure.clone()), Some(&self.to_string()));
 
-        for (idx, token) in self.declaration.params.iter().enumerate() {
-            let mut env_mut = env.borrow_mut();
-
-            env_mut.define(token.lexeme.clone(), args.get(idx).cloned());
+        for (param, slot) in self.declaration.params.iter().zip(slots) {
+            let value = match (slot, &param.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => evaluator.eval_in_env(default, env.clone())?,
+                (None, None) => Scalar::Nil,
+            };
+            env.borrow_mut().define(param.name.lexeme.clone(), Some(value));
         }
-        // let res = self.declaration.body.interpret(env.clone());
-        // println!("call user function: {} env: {}", self.to_string(), env.borrow());
-        let res = evaluator.eval_block(&self.declaration.fn_body, env.clone());
+        if let Some(rest_param) = &self.declaration.rest {
+            env.borrow_mut()
+                .define(rest_param.lexeme.clone(), Some(rest.into()));
+        }
+        Ok(env)
+    }
+    // 调用期间占一个栈帧，出错时调用栈留给 backtrace
+    fn invoke(
+        &self,
+        evaluator: &mut Evaluator,
+        slots: Vec<Option<Scalar>>,
+        rest: Vec<Scalar>,
+    ) -> InterpretResult<Scalar> {
+        evaluator.push_frame(self.declaration.clone(), self.owner.clone())?;
+        let res = self
+            .bind_params(evaluator, slots, rest)
+            .and_then(|env| evaluator.eval_block(&self.declaration.fn_body, env));
+        evaluator.pop_frame(&res);
         let Err(e) = res else {
             if self.is_init() && self.instance.is_some() {
                 return Ok(self.instance.as_ref().unwrap().borrow().clone());
@@ -65,8 +183,124 @@ impl Callable for UserFn {
         };
         Ok(ret)
     }
+}
+impl Callable for UserFn {
+    fn to_string(&self) -> String {
+        let name = &self.declaration.name.lexeme;
+        format!("<fn {}>", name)
+    }
 
-    fn arity(&self) -> usize {
-        self.declaration.params.len()
+    fn call(&self, evaluator: &mut Evaluator, args: Vec<Scalar>) -> InterpretResult<Scalar> {
+        let (slots, rest) = self.split_args(args);
+        let result = self.invoke(evaluator, slots, rest);
+        evaluator.finish_tail_calls(result)
     }
+
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Scalar>,
+        named: Vec<(Token, Scalar)>,
+        paren: &Token,
+    ) -> InterpretResult<Scalar> {
+        let result = self.enter(evaluator, args, named, paren);
+        evaluator.finish_tail_calls(result)
+    }
+
+    fn arity(&self) -> Arity {
+        self.declaration.arity()
+    }
+}
+
+#[test]
+fn tail_calls_do_not_grow_the_stack() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+fun count(n, acc) {
+  if (n == 0) return acc;
+  return count(n - 1, acc + 1);
+}
+var result = count(100000, 0);
+"#;
+    // 测试线程的栈只有 2MB，不做尾调用的话十万层会溢出
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "result"), Scalar::Number(100000.0));
+}
+
+#[test]
+fn rest_params_and_spread_arguments() {
+    use crate::test_util::{global, run_source, runtime_errors};
+
+    let src = r#"
+fun pack(first, ...rest) { return rest; }
+fun add(a, b, c) { return a + b + c; }
+var rest = pack(1, 2, 3);
+var empty = pack(1);
+var sum = add(...rest, 4);
+var kind = typeOf(...pack(0, "s"));
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    // 比较列表里的内容
+    let items = |name: &str| global(&evaluator, name).as_list().unwrap().borrow().clone();
+    assert_eq!(items("rest"), [Scalar::Number(2.0), Scalar::Number(3.0)]);
+    assert!(items("empty").is_empty());
+    assert_eq!(global(&evaluator, "sum"), Scalar::Number(9.0));
+    assert_eq!(global(&evaluator, "kind"), Scalar::String("string".into()));
+
+    assert_eq!(
+        runtime_errors("fun f(...xs) {}\nf(...1);"),
+        ["[line 2] Can only spread a list."]
+    );
+    assert_eq!(
+        runtime_errors("fun f(a, ...rest) {}\nf();"),
+        ["[line 2] Expected at least 1 arguments but got 0."]
+    );
+    assert_eq!(
+        runtime_errors("fun pack(...xs) { return xs; }\nclock(...pack(1));"),
+        ["[line 2] Expected 0 arguments but got 1."]
+    );
+}
+
+#[test]
+fn defaults_and_named_arguments() {
+    use crate::test_util::{global, run_source, runtime_errors};
+
+    let src = r#"
+class Counter { init() { this.n = 0; } }
+fun bump(c = Counter()) { c.n = c.n + 1; return c.n; }
+var first = bump();
+var second = bump();
+fun twice(a, b = a * 2) { return b; }
+var doubled = twice(4);
+var base = 1;
+fun offset(x = base) { return x; }
+var before = offset();
+base = 5;
+var after = offset();
+var named = twice(b: 3, a: 1);
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    // 默认值每次调用都重新求值，不会共享同一个实例
+    assert_eq!(global(&evaluator, "first"), Scalar::Number(1.0));
+    assert_eq!(global(&evaluator, "second"), Scalar::Number(1.0));
+    assert_eq!(global(&evaluator, "doubled"), Scalar::Number(8.0));
+    assert_eq!(global(&evaluator, "before"), Scalar::Number(1.0));
+    assert_eq!(global(&evaluator, "after"), Scalar::Number(5.0));
+    assert_eq!(global(&evaluator, "named"), Scalar::Number(3.0));
+
+    // 报错的行是调用所在的行
+    let fails = |src: &str, error: &str| assert_eq!(runtime_errors(src), [error]);
+    let fun = "fun f(a, b = 1) { return a + b; }\n";
+    fails(&format!("{}f(1, c: 2);", fun), "[line 2] Unknown argument 'c'.");
+    fails(&format!("{}f(1, a: 2);", fun), "[line 2] Duplicate argument 'a'.");
+    fails(&format!("{}f(b: 2);", fun), "[line 2] Missing argument 'a'.");
+    let class = "class P { init(x, y = 0) { this.x = x; } }\nclass Q {}\n";
+    fails(&format!("{}P(z: 1);", class), "[line 3] Unknown argument 'z'.");
+    fails(&format!("{}P(1, x: 1);", class), "[line 3] Duplicate argument 'x'.");
+    fails(&format!("{}P(y: 1);", class), "[line 3] Missing argument 'x'.");
+    fails(&format!("{}Q(x: 1);", class), "[line 3] Unknown argument 'x'.");
 }
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
+    callable::Callable,
     error::report_runtime,
-    evaluator::{InterpretError, InterpretResult},
+    evaluator::{Evaluator, InterpretError, InterpretResult},
     token::Token,
 };
 
@@ -10,27 +11,40 @@ use super::{ClassValue, Scalar};
 
 #[derive(Clone, Debug)]
 pub struct InstanceValue {
-    pub class: ClassValue,
+    pub class: Rc<ClassValue>,
     pub fields: HashMap<String, Scalar>,
 }
 impl InstanceValue {
-    pub fn new(class: ClassValue) -> Self {
+    pub fn new(class: Rc<ClassValue>) -> Self {
         Self {
             class,
             fields: HashMap::new(),
         }
     }
-    pub fn get(&self, token: &Token) -> InterpretResult<Scalar> {
+    // 接收 Rc 而不是 &self：绑定的 this 必须是同一个实例，getter 也可能修改字段
+    pub fn get(
+        instance: &Rc<RefCell<Self>>,
+        token: &Token,
+        evaluator: &mut Evaluator,
+    ) -> InterpretResult<Scalar> {
         let name = &token.lexeme;
-        let field = self.fields.get(name);
-        if let Some(field) = field {
-            return Ok(field.clone());
-        }
-
-        let method = self.class.find_method(name);
+        let method = {
+            let this = instance.borrow();
+            if let Some(field) = this.fields.get(name) {
+                return Ok(field.clone());
+            }
+            this.class.find_method(name)
+        };
 
         if let Some(method) = method {
-            return Ok(method.bind(self.clone().into()).into());
+            let method = method.bind(Scalar::Instance(instance.clone()));
+            if method.is_getter() {
+                evaluator.call_line = token.line;
+                let result = method.call(evaluator, vec![]);
+                evaluator.call_line = 0;
+                return result;
+            }
+            return Ok(method.into());
         }
         report_runtime(token.line, format!("Field {} not found", name));
         Err(InterpretError::Runtime(format!("Field {} not found", name)))
@@ -40,8 +54,3 @@ impl InstanceValue {
         self.fields.insert(name.to_string(), value);
     }
 }
-impl PartialEq for InstanceValue {
-    fn eq(&self, other: &Self) -> bool {
-        self.class == other.class
-    }
-}
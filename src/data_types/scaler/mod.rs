@@ -1,14 +1,16 @@
 use std::{
-    cell::RefCell, fmt::{Debug, Display}, ops::{Add, Div, Mul, Neg, Not, Sub}, rc::Rc
+    cell::RefCell, fmt::{Debug, Display}, hash::{Hash, Hasher}, ops::{Add, Div, Mul, Neg, Not, Sub}, rc::Rc
 };
 mod class_value;
 mod function_value;
 mod instance_value;
+mod trait_value;
 
 use crate::callable::Callable;
 pub use class_value::*;
 pub use function_value::*;
 pub use instance_value::*;
+pub use trait_value::*;
 
 // #[derive(Clone, PartialEq, PartialOrd)]
 pub enum Scalar {
@@ -16,8 +18,10 @@ pub enum Scalar {
     Number(f64),
     String(String),
     Function(FunctionValue),
-    Class(ClassValue),
+    Class(Rc<ClassValue>),
+    Trait(Rc<TraitValue>),
     Instance(Rc<RefCell<InstanceValue>>),
+    List(Rc<RefCell<Vec<Scalar>>>),
     Nil,
 }
 impl Clone for Scalar {
@@ -28,21 +32,56 @@ impl Clone for Scalar {
             Scalar::String(x) => Scalar::String(x.clone()),
             Scalar::Function(callable) => Scalar::Function(callable.clone()),
             Scalar::Class(class) => Scalar::Class(class.clone()),
+            Scalar::Trait(tr) => Scalar::Trait(tr.clone()),
             Scalar::Instance(instance) => Scalar::Instance(instance.clone()),
+            Scalar::List(list) => Scalar::List(list.clone()),
             Scalar::Nil => Scalar::Nil,
         }
     }
 }
 
+// 引用类型比较是不是同一个对象；NaN 等于 NaN、0 等于 -0，这样才能实现 Eq/Hash
 impl PartialEq for Scalar {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
-            (Self::Number(l0), Self::Number(r0)) => l0 == r0,
+            (Self::Number(l0), Self::Number(r0)) => l0 == r0 || (l0.is_nan() && r0.is_nan()),
             (Self::String(l0), Self::String(r0)) => l0 == r0,
-            (Self::Class(l0), Self::Class(r0)) => l0 == r0,
-            (Self::Instance(l0), Self::Instance(r0)) => l0 == r0,
-            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+            (Self::Function(l0), Self::Function(r0)) => l0 == r0,
+            (Self::Class(l0), Self::Class(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Trait(l0), Self::Trait(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Instance(l0), Self::Instance(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::List(l0), Self::List(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Nil, Self::Nil) => true,
+            _ => false,
+        }
+    }
+}
+impl Eq for Scalar {}
+// 和 PartialEq 保持一致：引用类型 hash 地址/id
+impl Hash for Scalar {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Scalar::Bool(b) => b.hash(state),
+            Scalar::Number(n) => {
+                let n = if n.is_nan() {
+                    f64::NAN
+                } else if *n == 0.0 {
+                    0.0
+                } else {
+                    *n
+                };
+                n.to_bits().hash(state)
+            }
+            Scalar::String(s) => s.hash(state),
+            Scalar::Function(FunctionValue::User(f)) => f.declaration_ptr().hash(state),
+            Scalar::Function(FunctionValue::Native(f)) => f.hash(state),
+            Scalar::Class(class) => Rc::as_ptr(class).hash(state),
+            Scalar::Trait(tr) => Rc::as_ptr(tr).hash(state),
+            Scalar::Instance(instance) => Rc::as_ptr(instance).hash(state),
+            Scalar::List(list) => Rc::as_ptr(list).hash(state),
+            Scalar::Nil => {}
         }
     }
 }
@@ -64,12 +103,18 @@ impl Scalar {
             _ => None,
         }
     }
-    pub(crate) fn as_class(&self) -> Option<&ClassValue> {
+    pub(crate) fn as_class(&self) -> Option<&Rc<ClassValue>> {
         match self {
             Scalar::Class(c) => Some(c),
             _ => None,
         }
     }
+    pub(crate) fn as_trait(&self) -> Option<Rc<TraitValue>> {
+        match self {
+            Scalar::Trait(tr) => Some(tr.clone()),
+            _ => None,
+        }
+    }
     pub(crate) fn as_bool(&self) -> Option<bool> {
         match self {
             Scalar::Bool(f) => Some(*f),
@@ -94,6 +139,38 @@ impl Scalar {
             _ => None,
         }
     }
+    pub(crate) fn as_list(&self) -> Option<Rc<RefCell<Vec<Scalar>>>> {
+        match self {
+            Scalar::List(list) => Some(list.clone()),
+            _ => None,
+        }
+    }
+    // `typeOf(x)` 的结果，脚本会拿来比较，不要随便改
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Scalar::Bool(_) => "bool",
+            Scalar::Number(_) => "number",
+            Scalar::String(_) => "string",
+            Scalar::Function(_) => "function",
+            Scalar::Class(_) => "class",
+            Scalar::Trait(_) => "trait",
+            Scalar::Instance(_) => "instance",
+            Scalar::List(_) => "list",
+            Scalar::Nil => "nil",
+        }
+    }
+    // `x is Shape`：x 是 Shape 或其子类的实例，或者混入了 trait Shape
+    pub(crate) fn is_a(&self, target: &Self) -> bool {
+        let Scalar::Instance(instance) = self else {
+            return false;
+        };
+        let class = &instance.borrow().class;
+        match target {
+            Scalar::Class(target) => class.is_subclass_of(target),
+            Scalar::Trait(target) => class.has_trait(target),
+            _ => false,
+        }
+    }
     pub(crate) fn is_same_type(left: &Self, other: &Self) -> bool {
         std::mem::discriminant(left) == std::mem::discriminant(other)
     }
@@ -175,7 +252,9 @@ impl Not for Scalar {
             Scalar::Nil => Scalar::Bool(true),
             Scalar::Function(_) => Scalar::Bool(false),
             Scalar::Class(_) => Scalar::Bool(false),
+            Scalar::Trait(_) => Scalar::Bool(false),
             Scalar::Instance(_) => Scalar::Bool(false),
+            Scalar::List(_) => Scalar::Bool(false),
         }
     }
 }
@@ -199,7 +278,12 @@ impl Display for Scalar {
             Scalar::Function(function_value) => function_value.to_string(),
             // Scalar::Class(class) => format!("class {}", class.name),
             Scalar::Class(class) => format!("{}", class.name),
+            Scalar::Trait(tr) => tr.name.clone(),
             Scalar::Instance(instance) => format!("{} instance", instance.borrow().class.name), 
+            Scalar::List(list) => {
+                let items: Vec<_> = list.borrow().iter().map(|item| item.to_string()).collect();
+                format!("[{}]", items.join(", "))
+            }
         };
         write!(f, "{}", s)
     }
@@ -213,7 +297,9 @@ impl Debug for Scalar {
             Scalar::Nil => "nil".to_string(),
             Scalar::Function(function_value) => format!("fn {}", function_value.to_string()),
             Scalar::Class(class) => format!("class {}", class.name),
+            Scalar::Trait(tr) => format!("trait {}", tr.name),
             Scalar::Instance(instance) => format!("instance {}", instance.borrow().class.name),
+            Scalar::List(list) => format!("list {:?}", list.borrow()),
         };
         write!(f, "{}", s)
     }
@@ -246,11 +332,60 @@ impl From<FunctionValue> for Scalar {
 }
 impl From<ClassValue> for Scalar {
     fn from(value: ClassValue) -> Self {
+        Self::Class(Rc::new(value))
+    }
+}
+impl From<Rc<ClassValue>> for Scalar {
+    fn from(value: Rc<ClassValue>) -> Self {
         Self::Class(value)
     }
 }
+impl From<TraitValue> for Scalar {
+    fn from(value: TraitValue) -> Self {
+        Self::Trait(Rc::new(value))
+    }
+}
+impl From<Vec<Scalar>> for Scalar {
+    fn from(value: Vec<Scalar>) -> Self {
+        Self::List(Rc::new(RefCell::new(value)))
+    }
+}
 impl From<InstanceValue> for Scalar {
     fn from(value: InstanceValue) -> Self {
         Self::Instance(Rc::new(RefCell::new(value)))
     }
 }
+
+#[test]
+fn hash_agrees_with_eq() {
+    use std::collections::hash_map::DefaultHasher;
+    fn hash(value: &Scalar) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+    let nan = Scalar::Number(f64::NAN);
+    assert_eq!(nan, Scalar::Number(-f64::NAN));
+    assert_eq!(hash(&nan), hash(&Scalar::Number(-f64::NAN)));
+    assert_eq!(Scalar::Number(0.0), Scalar::Number(-0.0));
+    assert_eq!(hash(&Scalar::Number(0.0)), hash(&Scalar::Number(-0.0)));
+
+    let class = Rc::new(ClassValue::new("A", None));
+    let a: Scalar = InstanceValue::new(class.clone()).into();
+    let b: Scalar = InstanceValue::new(class.clone()).into();
+    assert_eq!(a, a.clone());
+    assert_eq!(hash(&a), hash(&a.clone()));
+    assert_ne!(a, b);
+    assert_eq!(Scalar::from(class.clone()), Scalar::from(class));
+    assert_ne!(Scalar::from(ClassValue::new("A", None)), Scalar::from(ClassValue::new("A", None)));
+
+    // 内容相同的两个列表不相等，修改列表不影响 hash
+    let list: Scalar = vec![Scalar::Number(1.0)].into();
+    let same = list.clone();
+    assert_eq!(list, same);
+    assert_ne!(list, vec![Scalar::Number(1.0)].into());
+    let before = hash(&list);
+    list.as_list().unwrap().borrow_mut().push(Scalar::Nil);
+    assert_eq!(hash(&list), before);
+    assert_eq!(hash(&list), hash(&same));
+}
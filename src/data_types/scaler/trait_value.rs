@@ -0,0 +1,20 @@
+use std::collections::HashMap;
+
+use super::UserFn;
+
+// trait 不能实例化，只在类声明时把方法复制进 ClassValue
+#[derive(Clone, Debug)]
+pub struct TraitValue {
+    pub name: String,
+    pub methods: HashMap<String, UserFn>,
+    pub class_methods: HashMap<String, UserFn>,
+}
+impl TraitValue {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            methods: HashMap::new(),
+            class_methods: HashMap::new(),
+        }
+    }
+}
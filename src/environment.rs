@@ -9,7 +9,12 @@ pub type EnvironmentType = Rc<RefCell<Environment>>;
 pub struct Environment {
     name: String,
     enclosing: Option<EnvironmentType>,
+    // 只有全局作用域用名字查找
     values: HashMap<String, Scalar>,
+    // 局部变量按定义顺序放在 slots 里，下标就是 Resolver 算出来的 slot；
+    // names 和 slots 一一对应，只给不开 resolver 时按名字查找用
+    slots: Vec<Scalar>,
+    names: Vec<String>,
 }
 
 impl Environment {
@@ -18,6 +23,8 @@ impl Environment {
             name: name.as_ref().unwrap_or(&"default").to_string(),
             enclosing,
             values: HashMap::new(),
+            slots: vec![],
+            names: vec![],
         }))
     }
     pub fn global_env() -> EnvironmentType {
@@ -25,19 +32,54 @@ impl Environment {
             name: "global".to_string(),
             enclosing: None,
             values: HashMap::new(),
+            slots: vec![],
+            names: vec![],
         };
 
         global.define("clock", Some(NativeFn::Clock.into()));
         global.define("log", Some(NativeFn::Log.into()));
+        global.define("typeOf", Some(NativeFn::TypeOf.into()));
+        global.define("fields", Some(NativeFn::Fields.into()));
+        global.define("methods", Some(NativeFn::Methods.into()));
+        global.define("superclass", Some(NativeFn::Superclass.into()));
+        global.define("str", Some(NativeFn::Str.into()));
         Rc::new(RefCell::new(global))
     }
+    fn is_global(&self) -> bool {
+        self.enclosing.is_none()
+    }
+    // 当前这一层定义的名字，不包括外层
+    pub fn names(&self) -> Vec<String> {
+        if self.is_global() {
+            self.values.keys().cloned().collect()
+        } else {
+            self.names.clone()
+        }
+    }
+    // 局部作用域里追加到下一个 slot，定义顺序和 Resolver 声明的顺序一致
     pub fn define<T: AsRef<str>>(&mut self, name: T, value: Option<Scalar>) {
-        self.values
-            .insert(name.as_ref().to_string(), value.unwrap_or(Scalar::Nil));
+        let value = value.unwrap_or(Scalar::Nil);
+        if self.is_global() {
+            self.values.insert(name.as_ref().to_string(), value);
+        } else {
+            self.slots.push(value);
+            self.names.push(name.as_ref().to_string());
+        }
+    }
+    // 同一层里重复定义时后面的生效
+    fn local_index(&self, name: &str) -> Option<usize> {
+        self.names.iter().rposition(|n| n == name)
     }
     pub fn assign(&mut self, name: impl AsRef<str>, value: Scalar) -> Result<(), EnvErr> {
-        if self.values.contains_key(name.as_ref()) {
-            self.values.insert(name.as_ref().to_string(), value);
+        if self.is_global() {
+            if self.values.contains_key(name.as_ref()) {
+                self.values.insert(name.as_ref().to_string(), value);
+                return Ok(());
+            }
+            return Err(EnvErr::AccessUndefined);
+        }
+        if let Some(index) = self.local_index(name.as_ref()) {
+            self.slots[index] = value;
             return Ok(());
         }
         match &self.enclosing {
@@ -46,47 +88,48 @@ impl Environment {
         }
     }
     pub fn get(&self, name: impl AsRef<str>) -> Result<Scalar, EnvErr> {
-        if let Some(value) = self.values.get(name.as_ref()) {
-            return Ok(value.clone());
+        if self.is_global() {
+            return self
+                .values
+                .get(name.as_ref())
+                .cloned()
+                .ok_or(EnvErr::AccessUndefined);
+        }
+        if let Some(index) = self.local_index(name.as_ref()) {
+            return Ok(self.slots[index].clone());
         }
         match &self.enclosing {
             Some(parent) => {
-                // parent.borrow().get(name);
-                let a: &RefCell<Environment> = parent.borrow();
-                let b = a.borrow();
-                b.get(name)
+                let parent: &RefCell<Environment> = parent.borrow();
+                parent.borrow().get(name)
             }
             None => Err(EnvErr::AccessUndefined),
         }
     }
-    pub fn ancestor(&self, distance: usize) -> EnvironmentType {
-        assert!(distance > 0);
-        let mut env: EnvironmentType = self.enclosing.clone().unwrap();
-        for _ in 1..distance {
-            let _env: &RefCell<Environment> = env.borrow();
-            let _env: EnvironmentType = _env.borrow().enclosing.clone().unwrap();
-            env = _env;
-        }
-
-        env
-    }
 
-    pub fn get_at(&self, distance: usize, name: impl AsRef<str>) -> Result<Scalar, EnvErr> {
-        if distance == 0 {
-            return self.get(name);
+    // 往外走 depth 层，取第 slot 个局部变量
+    pub fn get_at(&self, depth: usize, slot: usize) -> Result<Scalar, EnvErr> {
+        if depth == 0 {
+            return self.slots.get(slot).cloned().ok_or(EnvErr::AccessUndefined);
+        }
+        match &self.enclosing {
+            Some(parent) => {
+                let parent: &RefCell<Environment> = parent.borrow();
+                parent.borrow().get_at(depth - 1, slot)
+            }
+            None => Err(EnvErr::AccessUndefined),
         }
-        self.ancestor(distance).borrow_mut().get(name)
     }
-    pub fn assign_at(
-        &mut self,
-        distance: usize,
-        name: impl AsRef<str>,
-        value: Scalar,
-    ) -> Result<(), EnvErr> {
-        if distance == 0 {
-            return self.assign(name, value);
+    pub fn assign_at(&mut self, depth: usize, slot: usize, value: Scalar) -> Result<(), EnvErr> {
+        if depth == 0 {
+            let target = self.slots.get_mut(slot).ok_or(EnvErr::AssignUndefined)?;
+            *target = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(parent) => parent.borrow_mut().assign_at(depth - 1, slot, value),
+            None => Err(EnvErr::AssignUndefined),
         }
-        self.ancestor(distance).borrow_mut().assign(name, value)
     }
 }
 impl Display for Environment {
@@ -97,7 +140,7 @@ impl Display for Environment {
                 f,
                 "({},{:?},[{}])",
                 self.name,
-                self.values.keys(),
+                self.names,
                 enclosing.borrow_mut()
             )
         } else {
@@ -116,3 +159,92 @@ impl Display for EnvErr {
     }
 }
 impl Error for EnvErr {}
+
+#[test]
+fn locals_resolve_with_and_without_resolver() {
+    use crate::{
+        evaluator::Evaluator,
+        test_util::{global, run_source_with},
+    };
+
+    let src = r#"
+var a = 1;
+var inner; var outer;
+{
+  var a = 2;
+  { var a = 3; inner = a; a = 30; inner = inner + a; }
+  outer = a;
+}
+fun counter() {
+  var n = 0;
+  fun next() { n = n + 1; return n; }
+  return next;
+}
+var c = counter();
+c(); c();
+var counted = c();
+var fns = nil;
+for (var i = 0; i < 3; i = i + 1) {
+  var j = i * 10;
+  fun get() { return j; }
+  if (i == 1) fns = get;
+}
+var captured = fns();
+class Point {
+  init(x) { this.x = x; }
+  sum(y) { var x = this.x; return x + y; }
+}
+var sum = Point(4).sum(5);
+"#;
+    let expected = [
+        ("a", 1.0),
+        ("inner", 33.0),
+        ("outer", 2.0),
+        ("counted", 3.0),
+        ("captured", 10.0),
+        ("sum", 9.0),
+    ];
+    // 不开 resolver 时按名字查找，结果要和按 slot 查找一样
+    for resolver in [true, false] {
+        let (evaluator, result) = run_source_with(Evaluator::new(resolver), src);
+        result.unwrap();
+        for (name, value) in expected {
+            assert_eq!(global(&evaluator, name), Scalar::Number(value), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn this_and_super_sit_in_slot_zero() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+class A {
+  init(x) { this.x = x; }
+  get() { return this.x; }
+}
+class B < A {
+  get() { var local = 1; return super.get() + local; }
+}
+var got = B(41).get();
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "got"), Scalar::Number(42.0));
+}
+
+#[test]
+fn redefinition_in_one_scope_uses_the_latest_slot() {
+    let scope = Environment::new(Some(Environment::global_env()), None);
+    let mut scope = scope.borrow_mut();
+    scope.define("a", Some(Scalar::Number(1.0)));
+    scope.define("a", Some(Scalar::Number(2.0)));
+    assert_eq!(scope.get("a").unwrap(), Scalar::Number(2.0));
+    scope.assign("a", Scalar::Number(3.0)).unwrap();
+    assert_eq!(scope.get_at(0, 0).unwrap(), Scalar::Number(1.0));
+    assert_eq!(scope.get_at(0, 1).unwrap(), Scalar::Number(3.0));
+    // 找不到的名字交给外层，全局也没有就报错
+    assert!(scope.get("clock").is_ok());
+    assert!(matches!(scope.get("b"), Err(EnvErr::AccessUndefined)));
+    assert!(scope.assign("b", Scalar::Nil).is_err());
+}
@@ -1,4 +1,4 @@
-use std::{error::Error, fmt::Display};
+use std::{cell::RefCell, error::Error, fmt::Display};
 
 use crate::{token::Token, token_type::TokenType};
 
@@ -37,30 +37,100 @@ impl Display for MyErrImpl {
 pub static mut HAD_ERROR: bool = false;
 pub static mut HAD_RUNTIME_ERROR: bool = false;
 
+// 错误或警告（包括运行时错误），capture_reports 期间不打印而是收集起来（lsp 用）
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub line: usize,
+    // 没有 token 的（比如扫描错误）只知道行号
+    pub token: Option<Token>,
+    pub message: String,
+    pub warning: bool,
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<Report>>> = const { RefCell::new(None) };
+}
+
+// 执行 f，期间的错误和警告收集起来返回；结束后 HAD_ERROR 复位，不影响下一次分析
+pub fn capture_reports<T>(f: impl FnOnce() -> T) -> (T, Vec<Report>) {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(vec![]));
+    let result = f();
+    let reports = CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default());
+    unsafe {
+        HAD_ERROR = false;
+    }
+    (result, reports)
+}
+
+// 正在收集时返回 true
+fn capture(report: &Report) -> bool {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(reports) => {
+            reports.push(report.clone());
+            true
+        }
+        None => false,
+    })
+}
+
 pub fn my_error_token(token: Token, message: String) {
-    if token.t_type == TokenType::EOF {
-        report(token.line, " at end".to_string(), message);
+    let r#where = if token.t_type == TokenType::EOF {
+        " at end".to_string()
     } else {
-        report(token.line, format!(" at '{}'", token.lexeme), message);
+        format!(" at '{}'", token.lexeme)
+    };
+    report(token.line, r#where, Some(token), message);
+}
+// 警告只打印，不影响退出码
+pub fn warning_token(token: Token, message: String) {
+    let text = format!(
+        "[line {}] Warning at '{}': {}",
+        token.line, token.lexeme, message
+    );
+    let report = Report {
+        line: token.line,
+        token: Some(token),
+        message,
+        warning: true,
+    };
+    if !capture(&report) {
+        eprintln!("{}", text);
     }
 }
 pub fn my_error(line: usize, message: String) {
-    report(line, "".to_string(), message);
+    report(line, "".to_string(), None, message);
 }
 
 pub fn unexpected_terminal_err(line: usize) {
     my_error(line, "Unterminated string.".to_string());
 }
 
-fn report(line: usize, r#where: String, message: String) {
+fn report(line: usize, r#where: String, token: Option<Token>, message: String) {
     let msg = format!("[line {}] Error{}: {}", line, r#where, message);
-    eprintln!("{}", msg);
+    let report = Report {
+        line,
+        token,
+        message,
+        warning: false,
+    };
+    if !capture(&report) {
+        eprintln!("{}", msg);
+    }
     unsafe {
         HAD_ERROR = true;
     };
 }
 pub fn report_runtime(line: usize, message: String) {
     let msg = format!("{}[line {}] ", message, line,);
+    let report = Report {
+        line,
+        token: None,
+        message,
+        warning: false,
+    };
+    if capture(&report) {
+        return;
+    }
 
     eprintln!("{}", msg);
     unsafe {
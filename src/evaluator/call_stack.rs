@@ -0,0 +1,146 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::{error::report_runtime, stmt::function::FunctionStmt, InterpretRtErr};
+
+use super::{error::InterpretResult, Evaluator, InterpretError};
+
+// 调用栈上的一个用户函数
+#[derive(Clone)]
+pub struct Frame {
+    pub function: Rc<FunctionStmt>,
+    // 声明方法的类或 trait，普通函数为 None
+    pub class: Option<Rc<str>>,
+    // 调用处的行号，不知道时为 0（比如 print 隐式调用 toString）
+    pub line: usize,
+}
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line > 0 {
+            write!(f, "[line {}] ", self.line)?;
+        }
+        if let Some(class) = &self.class {
+            write!(f, "{}.", class)?;
+        }
+        write!(f, "{}()", self.function.name.lexeme)
+    }
+}
+
+impl Evaluator {
+    // 每次进入用户函数都经过这里，太深时报运行时错误；出错时没有压栈，调用方不要 pop_frame
+    pub(crate) fn push_frame(
+        &mut self,
+        function: Rc<FunctionStmt>,
+        class: Option<Rc<str>>,
+    ) -> InterpretResult<()> {
+        let line = std::mem::take(&mut self.call_line);
+        if self.frames.len() >= self.max_depth {
+            // 隐式调用（比如 toString）不知道调用处，报在函数声明的那一行
+            let line = if line > 0 { line } else { function.name.line };
+            report_runtime(line, "Stack overflow.".to_string());
+            return InterpretRtErr!(;"stack overflow");
+        }
+        self.frames.push(Frame {
+            function,
+            class,
+            line,
+        });
+        Ok(())
+    }
+    // 出错时最里层的函数先看到错误，这时的栈就是完整的调用链
+    pub(crate) fn pop_frame<T>(&mut self, result: &InterpretResult<T>) {
+        let failed = matches!(
+            result,
+            Err(InterpretError::Runtime(_) | InterpretError::Other)
+        );
+        if failed && self.backtrace.is_none() {
+            self.backtrace = Some(self.frames.clone());
+        }
+        self.frames.pop();
+    }
+    // 最近一次运行时错误发生时的调用栈，取出后清空
+    pub fn take_backtrace(&mut self) -> Vec<Frame> {
+        self.backtrace.take().unwrap_or_default()
+    }
+}
+
+// 和 Python 一样最外层的调用在前，连续相同的栈帧合并成一行；尾调用复用了栈帧，中间的调用不会出现
+pub fn print_backtrace(frames: &[Frame]) {
+    if frames.is_empty() {
+        return;
+    }
+    eprintln!("Traceback (most recent call last):");
+    let lines: Vec<_> = frames.iter().map(|frame| frame.to_string()).collect();
+    for group in lines.chunk_by(|a, b| a == b) {
+        eprintln!("  {}", group[0]);
+        if group.len() > 1 {
+            eprintln!("  [Previous frame repeated {} more times]", group.len() - 1);
+        }
+    }
+}
+
+#[cfg(test)]
+fn backtrace_lines(evaluator: &mut Evaluator) -> Vec<String> {
+    let frames = evaluator.take_backtrace();
+    frames.iter().map(|frame| frame.to_string()).collect()
+}
+
+#[test]
+fn backtrace_lists_calls_from_outermost() {
+    let src = r#"
+class Box {
+  open() { fail(); }
+}
+fun fail() { var x = nil + 1; }
+fun run() { Box().open(); }
+run();
+"#;
+    let (mut evaluator, result) = crate::test_util::run_source(src);
+    assert!(result.is_err());
+    assert_eq!(
+        backtrace_lines(&mut evaluator),
+        ["[line 7] run()", "[line 6] Box.open()", "[line 3] fail()"]
+    );
+    assert!(evaluator.frames.is_empty());
+}
+
+#[test]
+fn backtrace_names_the_declaring_class() {
+    let src = r#"
+trait Loud { shout() { Base.fail(); } }
+class Base {
+  class fail() { return nil - 1; }
+  run() { this.shout(); }
+}
+class Derived < Base with Loud {}
+Derived().run();
+"#;
+    let (mut evaluator, result) = crate::test_util::run_source(src);
+    assert!(result.is_err());
+    // 继承来的方法显示声明它的类，trait 方法显示 trait，类方法也带类名
+    assert_eq!(
+        backtrace_lines(&mut evaluator),
+        [
+            "[line 8] Base.run()",
+            "[line 5] Loud.shout()",
+            "[line 2] Base.fail()"
+        ]
+    );
+}
+
+#[test]
+fn deep_recursion_stops_at_max_depth() {
+    use crate::test_util::run_source_with;
+
+    let overflows = |src: &str| {
+        // 测试线程的栈只有 2MB，上限要设得小一些
+        let evaluator = Evaluator::new(true).with_max_depth(20);
+        let (mut evaluator, result) = run_source_with(evaluator, src);
+        assert!(matches!(result, Err(InterpretError::Runtime(msg)) if msg == "stack overflow"));
+        assert_eq!(evaluator.take_backtrace().len(), 20);
+    };
+    overflows("fun f(n) { return 1 + f(n + 1); } f(0);");
+    // getter 和隐式的 toString 不经过调用表达式，也要检查
+    overflows("class A { x { return this.x; } } print A().x;");
+    overflows(r#"class A { toString() { return "" + this; } } print A();"#);
+    overflows("class V { __neg__() { return -this; } } -V();");
+}
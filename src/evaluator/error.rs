@@ -1,13 +1,27 @@
 use std::{error::Error, fmt::Display};
 
-use crate::data_types::scaler::Scalar;
+use crate::{
+    data_types::scaler::{Scalar, UserFn},
+    token::Token,
+};
 pub type InterpretResult<T> = Result<T, InterpretError>;
 
 #[derive(Debug)]
 pub enum InterpretError {
+    // 已经报告过的运行时错误，调用处不要再报一次
     Other,
     Runtime(String),
     Return(Scalar),
+    // 函数里的 `return f(...)`，由调用方在循环里接着调用，不加深 Rust 栈
+    TailCall(Box<TailCall>),
+}
+
+#[derive(Debug)]
+pub struct TailCall {
+    pub function: UserFn,
+    pub args: Vec<Scalar>,
+    pub named: Vec<(Token, Scalar)>,
+    pub paren: Token,
 }
 impl InterpretError {
     pub fn rt<T: AsRef<str>>(msg: T) -> Self {
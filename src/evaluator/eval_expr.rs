@@ -1,14 +1,18 @@
 use crate::{
     callable::Callable,
-    data_types::scaler::Scalar,
+    data_types::scaler::{FunctionValue, InstanceValue, Scalar},
     error::report_runtime,
-    expr::{binary::BinaryExpr, Expr},
+    expr::{binary::BinaryExpr, call::CallExpr, Expr},
+    token::Token,
     token_type::TokenType,
     InterpretRtErr,
 };
 
 use super::{error::InterpretResult, Evaluator, InterpretError, Interprete};
 
+// 求值好的被调用者、位置参数和具名参数
+pub(crate) type CallParts = (Scalar, Vec<Scalar>, Vec<(Token, Scalar)>);
+
 impl Interprete<Expr> for Evaluator {
     type Output = InterpretResult<Scalar>;
     fn eval(&mut self, expr: &Expr) -> InterpretResult<Scalar> {
@@ -21,12 +25,24 @@ impl Interprete<Expr> for Evaluator {
                 } = binary.as_ref();
                 let left = self.eval(left)?;
                 let right = self.eval(right)?;
+                if let Some(result) = self.binary_operator_method(&left, &right, operator)? {
+                    return Ok(result);
+                }
                 match operator.t_type {
                     TokenType::MINUS => {
                         self.check_number_operands(&left, &right, operator)?;
                         Ok(left - right)
                     }
                     TokenType::PLUS => {
+                        // 字符串和实例拼接时用 toString()
+                        if matches!(
+                            (&left, &right),
+                            (Scalar::String(_), Scalar::Instance(_))
+                                | (Scalar::Instance(_), Scalar::String(_))
+                        ) {
+                            let s = self.stringify(&left)? + &self.stringify(&right)?;
+                            return Ok(Scalar::String(s));
+                        }
                         if !matches!(
                             (&left, &right),
                             (Scalar::String(_), Scalar::String(_))
@@ -66,6 +82,16 @@ impl Interprete<Expr> for Evaluator {
                         self.check_number_operands(&left, &right, operator)?;
                         Ok(Scalar::Bool(left <= right))
                     }
+                    TokenType::IS => {
+                        if !matches!(right, Scalar::Class(_) | Scalar::Trait(_)) {
+                            report_runtime(
+                                operator.line,
+                                "Right operand of 'is' must be a class or trait.".to_string(),
+                            );
+                            return InterpretRtErr!(;"bad is");
+                        }
+                        Ok(Scalar::Bool(left.is_a(&right)))
+                    }
                     _ => Ok(Scalar::Nil),
                 }
             }
@@ -75,6 +101,11 @@ impl Interprete<Expr> for Evaluator {
                 TokenType::BANG => Ok(!self.eval(&unary.right)?),
                 TokenType::MINUS => {
                     let right = self.eval(&unary.right)?;
+                    let method =
+                        self.call_operator_method(&right, "__neg__", vec![], &unary.operator);
+                    if let Some(result) = method? {
+                        return Ok(result);
+                    }
                     self.check_number_operand(&right, &unary.operator)?;
                     Ok(-right)
                 }
@@ -113,66 +144,54 @@ impl Interprete<Expr> for Evaluator {
                 let left = self.eval(&logical.left)?;
                 let left_condition = (!!left.clone()).as_bool().unwrap();
                 match logical.operator.t_type {
+                    TokenType::QuestionQuestion if left != Scalar::Nil => Ok(left),
+                    TokenType::QuestionQuestion => self.eval(&logical.right),
                     TokenType::OR if left_condition => Ok(left),
                     TokenType::AND if !left_condition => Ok(left),
                     _ => self.eval(&logical.right),
                 }
             }
-            Expr::Call(call) => {
-                let callee = self.eval(&call.callee)?;
-                // println!("callee: {:?}", callee);
-                let mut args = Vec::new();
-                for arg in &call.arguments {
-                    args.push(self.eval(arg)?);
-                }
-
-                let function = callee.as_callable().ok_or_else(|| {
-                    report_runtime(
-                        call.parent.line,
-                        "Can only call functions and classes.".to_string(),
-                    );
-                    InterpretError::rt("Can only call functions and classes.")
-                })?;
-
-                // Check if number of arguments matches
-                if args.len() != function.arity() {
-                    report_runtime(
-                        call.parent.line,
-                        format!(
-                            "Expected {} arguments but got {}.",
-                            function.arity(),
-                            args.len()
-                        ),
-                    );
-                    return InterpretRtErr!(;"wrong number of arguments");
-                }
-
-                function.call(self, args)
+            Expr::Call(_) | Expr::Get(_) | Expr::Set(_) => {
+                Ok(self.eval_chain(expr)?.unwrap_or(Scalar::Nil))
             }
-            Expr::Get(get) => {
-                let object = self.eval(&get.object)?;
-                let instance = object.as_instance();
-                if let Some(instance) = instance {
-                    let instance = instance.borrow();
-                    instance.get(&get.name)
-                } else {
-                    report_runtime(get.name.line, format!("Only instances have properties."));
-                    InterpretRtErr!(;format!("bad get: {}", get.name.lexeme))
+            Expr::Index(index) => {
+                let object = self.eval(&index.object)?;
+                let position = self.eval(&index.index)?;
+                let method = self.call_operator_method(
+                    &object,
+                    "__index__",
+                    vec![position.clone()],
+                    &index.bracket,
+                );
+                if let Some(result) = method? {
+                    return Ok(result);
                 }
-            }
-            Expr::Set(set) => {
-                let object = self.eval(&set.object)?;
-                let value = self.eval(&set.value)?;
-                let instance = object.as_instance();
-                if let Some(instance) = &instance {
-                    // instance.set(name, value.clone());
-                    instance.borrow_mut().set(&set.name, value.clone());
-                    Ok(value)
-                } else {
-                    report_runtime(set.name.line, "Only instances have properties.".to_string());
-                    InterpretRtErr!(;format!("bad set {}.", set.name.lexeme))
+                let Some(list) = object.as_list() else {
+                    report_runtime(index.bracket.line, "Can only index lists.".to_string());
+                    return InterpretRtErr!(;"bad index");
+                };
+                let list = list.borrow();
+                match position.as_number() {
+                    Some(n) if n.fract() == 0.0 && n >= 0.0 && (n as usize) < list.len() => {
+                        Ok(list[n as usize].clone())
+                    }
+                    Some(n) if n.fract() == 0.0 => {
+                        report_runtime(index.bracket.line, "Index out of range.".to_string());
+                        InterpretRtErr!(;"index out of range")
+                    }
+                    _ => {
+                        report_runtime(index.bracket.line, "Index must be an integer.".to_string());
+                        InterpretRtErr!(;"bad index")
+                    }
                 }
             }
+            Expr::Spread(spread) => {
+                report_runtime(
+                    spread.ellipsis.line,
+                    "Can only spread into call arguments.".to_string(),
+                );
+                InterpretRtErr!(;"bad spread")
+            }
             Expr::This(this) => {
                 let name = &this.keyword.lexeme;
                 let value = self.lookup_variable(expr, name);
@@ -186,20 +205,28 @@ impl Interprete<Expr> for Evaluator {
             }
             Expr::Super(super_expr) => {
                 let method_name = &super_expr.method.lexeme;
-                let distance = self
-                    .get_depth(expr)
+                let (depth, slot) = self
+                    .get_local(expr)
                     .expect("Superclass distance not found.");
                 let sup_class = self
                     .env
                     .borrow()
-                    .get_at(distance, "super")
+                    .get_at(depth, slot)
                     .expect("super not found.");
-                let sup_class = sup_class.as_class().expect("super is not a class.");
+                // 混入 trait 的类没有超类时，trait 方法里的 super 是 nil
+                let Some(sup_class) = sup_class.as_class() else {
+                    report_runtime(
+                        super_expr.keyword.line,
+                        "Can't use 'super' in a class with no superclass.".to_string(),
+                    );
+                    return InterpretRtErr!(;"bad super");
+                };
                 let sup_method = sup_class.find_method(method_name);
                 let this_instance = self
                     .env
                     .borrow()
-                    .get_at(distance - 1, "this")
+                    // this 是 super 里面那层作用域的第一个变量
+                    .get_at(depth - 1, 0)
                     .expect("this not found.");
 
                 match sup_method {
@@ -216,3 +243,301 @@ impl Interprete<Expr> for Evaluator {
         }
     }
 }
+
+impl Evaluator {
+    // 类定义了对应的 `__xxx__` 方法时调用，返回 None 走内置规则；`==` 左边没有 `__eq__` 时再看右边
+    fn binary_operator_method(
+        &mut self,
+        left: &Scalar,
+        right: &Scalar,
+        operator: &Token,
+    ) -> InterpretResult<Option<Scalar>> {
+        let name = match operator.t_type {
+            TokenType::PLUS => "__add__",
+            TokenType::MINUS => "__sub__",
+            TokenType::STAR => "__mul__",
+            TokenType::SLASH => "__div__",
+            TokenType::LESS => "__lt__",
+            TokenType::LessEqual => "__le__",
+            TokenType::GREATER => "__gt__",
+            TokenType::GreaterEqual => "__ge__",
+            TokenType::EqualEqual | TokenType::BangEqual => {
+                let mut result =
+                    self.call_operator_method(left, "__eq__", vec![right.clone()], operator)?;
+                if result.is_none() {
+                    result =
+                        self.call_operator_method(right, "__eq__", vec![left.clone()], operator)?;
+                }
+                let Some(result) = result else {
+                    return Ok(None);
+                };
+                let equal = (!!result).as_bool().unwrap();
+                let equal = if operator.t_type == TokenType::BangEqual {
+                    !equal
+                } else {
+                    equal
+                };
+                return Ok(Some(Scalar::Bool(equal)));
+            }
+            _ => return Ok(None),
+        };
+        self.call_operator_method(left, name, vec![right.clone()], operator)
+    }
+    fn call_operator_method(
+        &mut self,
+        object: &Scalar,
+        name: &str,
+        args: Vec<Scalar>,
+        operator: &Token,
+    ) -> InterpretResult<Option<Scalar>> {
+        let Some(instance) = object.as_instance() else {
+            return Ok(None);
+        };
+        let Some(method) = instance.borrow().class.find_method(name) else {
+            return Ok(None);
+        };
+        if !method.arity().accepts(args.len()) {
+            let message = format!(
+                "Expected {} arguments but got {}.",
+                method.arity(),
+                args.len()
+            );
+            report_runtime(operator.line, message);
+            return InterpretRtErr!(;"wrong number of arguments");
+        }
+        self.call_line = operator.line;
+        let result = method.bind(object.clone()).call(self, args);
+        self.call_line = 0;
+        result.map(Some)
+    }
+    // 求值 `a.b(c)?.d` 这样的调用链，`?.` 遇到 nil 时返回 None，整条链短路
+    fn eval_chain(&mut self, expr: &Expr) -> InterpretResult<Option<Scalar>> {
+        match expr {
+            Expr::Call(call) => {
+                let Some((callee, args, named)) = self.eval_call_parts(call)? else {
+                    return Ok(None);
+                };
+                self.call_value(callee, args, named, &call.parent).map(Some)
+            }
+            Expr::Get(get) => {
+                let Some(object) = self.eval_link(&get.object)? else {
+                    return Ok(None);
+                };
+                if get.optional && object == Scalar::Nil {
+                    return Ok(None);
+                }
+                if let Some(class) = object.as_class() {
+                    if let Some(value) = class.get_field(&get.name.lexeme) {
+                        return Ok(Some(value));
+                    }
+                    return match class.find_class_method(&get.name.lexeme) {
+                        Some(method) => Ok(Some(method.into())),
+                        None => {
+                            let message = format!("Field {} not found", get.name.lexeme);
+                            report_runtime(get.name.line, message.clone());
+                            InterpretRtErr!(;message)
+                        }
+                    };
+                }
+                let instance = object.as_instance();
+                if let Some(instance) = instance {
+                    InstanceValue::get(&instance, &get.name, self).map(Some)
+                } else {
+                    report_runtime(get.name.line, format!("Only instances have properties."));
+                    InterpretRtErr!(;format!("bad get: {}", get.name.lexeme))
+                }
+            }
+            Expr::Set(set) => {
+                let Some(object) = self.eval_link(&set.object)? else {
+                    return Ok(None);
+                };
+                if set.optional && object == Scalar::Nil {
+                    return Ok(None);
+                }
+                let value = self.eval(&set.value)?;
+                if let Some(class) = object.as_class() {
+                    class.set_field(&set.name.lexeme, value.clone());
+                    return Ok(Some(value));
+                }
+                let instance = object.as_instance();
+                if let Some(instance) = &instance {
+                    // instance.set(name, value.clone());
+                    instance.borrow_mut().set(&set.name, value.clone());
+                    Ok(Some(value))
+                } else {
+                    report_runtime(set.name.line, "Only instances have properties.".to_string());
+                    InterpretRtErr!(;format!("bad set {}.", set.name.lexeme))
+                }
+            }
+            _ => self.eval(expr).map(Some),
+        }
+    }
+    // 求值被调用者和参数，`f?.()` 中 f 为 nil 时返回 None
+    pub(crate) fn eval_call_parts(
+        &mut self,
+        call: &CallExpr,
+    ) -> InterpretResult<Option<CallParts>> {
+        let Some(callee) = self.eval_link(&call.callee)? else {
+            return Ok(None);
+        };
+        if call.optional && callee == Scalar::Nil {
+            return Ok(None);
+        }
+        let mut args = Vec::new();
+        for arg in &call.arguments {
+            if let Expr::Spread(spread) = arg {
+                let value = self.eval(&spread.expression)?;
+                let Some(list) = value.as_list() else {
+                    report_runtime(spread.ellipsis.line, "Can only spread a list.".to_string());
+                    return InterpretRtErr!(;"bad spread");
+                };
+                args.extend(list.borrow().iter().cloned());
+            } else {
+                args.push(self.eval(arg)?);
+            }
+        }
+        let mut named = Vec::new();
+        for arg in &call.named {
+            named.push((arg.name.clone(), self.eval(&arg.value)?));
+        }
+        Ok(Some((callee, args, named)))
+    }
+    pub(crate) fn call_value(
+        &mut self,
+        callee: Scalar,
+        args: Vec<Scalar>,
+        named: Vec<(Token, Scalar)>,
+        paren: &Token,
+    ) -> InterpretResult<Scalar> {
+        let function = callee.as_callable().ok_or_else(|| {
+            report_runtime(
+                paren.line,
+                "Can only call functions and classes.".to_string(),
+            );
+            InterpretError::rt("Can only call functions and classes.")
+        })?;
+
+        if !named.is_empty() {
+            self.call_line = paren.line;
+            let result = function.call_named(self, args, named, paren);
+            self.call_line = 0;
+            return result;
+        }
+
+        // Check if number of arguments matches
+        if !function.arity().accepts(args.len()) {
+            report_runtime(
+                paren.line,
+                format!(
+                    "Expected {} arguments but got {}.",
+                    function.arity(),
+                    args.len()
+                ),
+            );
+            return InterpretRtErr!(;"wrong number of arguments");
+        }
+
+        self.call_line = paren.line;
+        let result = function.call(self, args);
+        self.call_line = 0;
+        if let Err(InterpretError::Runtime(msg)) = &result {
+            if matches!(callee, Scalar::Function(FunctionValue::Native(_))) {
+                report_runtime(paren.line, msg.clone());
+            }
+        }
+        result
+    }
+    // 括号会打断调用链：`(a?.b).c` 中 a 为 nil 时仍然报错
+    fn eval_link(&mut self, object: &Expr) -> InterpretResult<Option<Scalar>> {
+        match object {
+            Expr::Call(_) | Expr::Get(_) => self.eval_chain(object),
+            _ => self.eval(object).map(Some),
+        }
+    }
+}
+
+#[test]
+fn operators_call_special_methods() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+class Money {
+  init(cents) { this.cents = cents; }
+  __add__(other) { return Money(this.cents + other.cents); }
+  __neg__() { return Money(-this.cents); }
+  __eq__(other) { return other is Money and this.cents == other.cents; }
+}
+class Plain {}
+var sum = (Money(150) + Money(250)).cents;
+var negated = (-Money(3)).cents;
+var equal = Money(1) == Money(1);
+var unequal = Money(1) != Money(1);
+var right = Plain() == Money(1);
+var plain = Plain();
+var same = plain == plain;
+var different = Plain() == Plain();
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "sum"), Scalar::Number(400.0));
+    assert_eq!(global(&evaluator, "negated"), Scalar::Number(-3.0));
+    assert_eq!(global(&evaluator, "equal"), Scalar::Bool(true));
+    assert_eq!(global(&evaluator, "unequal"), Scalar::Bool(false));
+    // 左边没有 __eq__ 时用右边的，两边都没有时按引用比较
+    assert_eq!(global(&evaluator, "right"), Scalar::Bool(false));
+    assert_eq!(global(&evaluator, "same"), Scalar::Bool(true));
+    assert_eq!(global(&evaluator, "different"), Scalar::Bool(false));
+
+    let (_, result) = run_source("class Plain {} Plain() + 1;");
+    assert!(matches!(result, Err(InterpretError::Runtime(_))));
+}
+
+#[test]
+fn index_calls_special_method_or_reads_lists() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+class Grid { __index__(i) { return i * 10; } }
+fun second(...items) { return items[1]; }
+var cell = Grid()[4];
+var item = second("a", "b", "c");
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "cell"), Scalar::Number(40.0));
+    assert_eq!(global(&evaluator, "item"), Scalar::String("b".into()));
+
+    let fails = |src: &str| {
+        let (_, result) = run_source(src);
+        assert!(matches!(result, Err(InterpretError::Runtime(_))), "{}", src);
+    };
+    fails("fun f(...xs) { return xs[3]; } f(1);");
+    fails("fun f(...xs) { return xs[0.5]; } f(1);");
+    fails("class A {} A()[0];");
+}
+
+#[test]
+fn optional_chains_and_nil_coalescing_short_circuit() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+var calls = 0;
+fun touch() { calls = calls + 1; return calls; }
+var a = nil;
+var chained = a?.b.c();
+var skipped = a?.f(touch());
+var falsy = false ?? touch();
+var kept = 1 ?? touch();
+var fallback = nil ?? "x";
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    // nil 之后整条链都不求值，参数也不求值
+    assert_eq!(global(&evaluator, "chained"), Scalar::Nil);
+    assert_eq!(global(&evaluator, "skipped"), Scalar::Nil);
+    // ?? 只在左边是 nil 时才求值右边
+    assert_eq!(global(&evaluator, "falsy"), Scalar::Bool(false));
+    assert_eq!(global(&evaluator, "kept"), Scalar::Number(1.0));
+    assert_eq!(global(&evaluator, "fallback"), Scalar::String("x".into()));
+    assert_eq!(global(&evaluator, "calls"), Scalar::Number(0.0));
+}
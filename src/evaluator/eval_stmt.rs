@@ -1,12 +1,13 @@
 use crate::{
-    data_types::scaler::{ClassValue, Scalar, UserFn},
+    data_types::scaler::{ClassValue, FunctionValue, MixedIn, Scalar, TraitValue, UserFn},
     environment::Environment,
     error::report_runtime,
+    expr::Expr,
     stmt::Stmt,
     InterpretRet, InterpretRtErr,
 };
 
-use super::{error::InterpretResult, Evaluator, InterpretError, Interprete};
+use super::{error::InterpretResult, Evaluator, InterpretError, Interprete, TailCall};
 
 impl Interprete<Stmt> for Evaluator {
     type Output = InterpretResult<()>;
@@ -28,7 +29,7 @@ impl Interprete<Stmt> for Evaluator {
 
             Stmt::Print(print) => {
                 let value = self.eval(&print.expression)?;
-                println!("{}", value);
+                println!("{}", self.stringify(&value)?);
                 Ok(())
             }
             Stmt::If(if_stmt) => {
@@ -50,6 +51,38 @@ impl Interprete<Stmt> for Evaluator {
                 }
                 Ok(())
             }
+            Stmt::For(for_stmt) => {
+                let old_env = self.env.clone();
+                self.env = Environment::new(Some(old_env.clone()), Some("for env"));
+                let result = (|| {
+                    if let Some(initializer) = &for_stmt.initializer {
+                        self.eval(initializer)?;
+                    }
+                    loop {
+                        if let Some(condition) = &for_stmt.condition {
+                            if !(!!self.eval(condition)?).as_bool().unwrap() {
+                                break;
+                            }
+                        }
+                        self.eval(&for_stmt.body)?;
+                        if let Some(increment) = &for_stmt.increment {
+                            self.eval(increment)?;
+                        }
+                    }
+                    Ok(())
+                })();
+                self.env = old_env;
+                result
+            }
+            Stmt::DoWhile(do_while) => {
+                loop {
+                    self.eval(&do_while.body)?;
+                    if !(!!self.eval(&do_while.condition)?).as_bool().unwrap() {
+                        break;
+                    }
+                }
+                Ok(())
+            }
             Stmt::Function(func) => {
                 let name = &func.name.lexeme;
                 let fun = UserFn::new(self.env.clone(), func.clone());
@@ -61,6 +94,23 @@ impl Interprete<Stmt> for Evaluator {
                 Environment::new(Some(self.env.clone()), None),
             ),
             Stmt::Return(ret) => {
+                if let (Some(Expr::Call(call)), true) = (&ret.value, !self.frames.is_empty()) {
+                    let Some((callee, args, named)) = self.eval_call_parts(call)? else {
+                        return InterpretRet!(Scalar::Nil);
+                    };
+                    // 用户函数交给外层的 finish_tail_calls 调用，其它的直接调用
+                    let Scalar::Function(FunctionValue::User(function)) = callee else {
+                        let value = self.call_value(callee, args, named, &call.parent)?;
+                        return InterpretRet!(value);
+                    };
+                    let paren = call.parent.clone();
+                    return Err(InterpretError::TailCall(Box::new(TailCall {
+                        function,
+                        args,
+                        named,
+                        paren,
+                    })));
+                }
                 let value = match &ret.value {
                     Some(expr) => self.eval(expr)?,
                     None => Scalar::Nil,
@@ -69,7 +119,6 @@ impl Interprete<Stmt> for Evaluator {
             }
             Stmt::Class(class) => {
                 let enclosing_env = self.env.clone();
-                let has_superclass = class.superclass.is_some();
                 let name = &class.name.lexeme;
 
                 let super_class = if let Some(super_class) = &class.superclass {
@@ -80,35 +129,162 @@ impl Interprete<Stmt> for Evaluator {
                         return InterpretRtErr!(;"superclass must be a class");
                     };
 
-                    // let super_class = super_value.clone();
-
                     Some(super_value.clone())
                 } else {
                     None
                 };
-                // if has_superclass {
-                //     self.env = Environment::new(Some(enclosing_env.clone()), Some("super env"));
-                //     self.env
-                //         .borrow_mut()
-                //         .define("super", Some(super_class.clone().unwrap().into()));
-                // }
-                let mut class_value = ClassValue::new(name, super_class.map(|s| s.into()));
-                let fn_env = if has_superclass {
+                let fn_env = if let Some(super_class) = &super_class {
                     let super_env = Environment::new(Some(self.env.clone()), Some("super env"));
                     super_env
                         .borrow_mut()
-                        .define("super", Some(class_value.clone().into()));
+                        .define("super", Some(super_class.clone().into()));
                     super_env
                 } else {
                     self.env.clone()
                 };
+                let mut class_value = ClassValue::new(name, super_class);
                 for function in &class.methods {
-                    let fun = UserFn::new(fn_env.clone(), function.clone());
+                    let fun = UserFn::method(fn_env.clone(), function.clone(), name);
                     class_value.add_method(&function.name.lexeme, fun);
                 }
+                for function in &class.class_methods {
+                    let fun = UserFn::method(fn_env.clone(), function.clone(), name);
+                    class_value.add_class_method(&function.name.lexeme, fun);
+                }
+                // trait 方法复制进类里，之后的 bind/super 都和普通方法一样
+                let mut mixed = MixedIn::new();
+                for trait_expr in &class.traits {
+                    // run-ast 读进来的 AST 不一定是变量，这时报在类名那一行
+                    let line = trait_expr
+                        .to_variable()
+                        .map_or(class.name.line, |variable| variable.name.line);
+                    let Some(tr) = self.eval(trait_expr)?.as_trait() else {
+                        report_runtime(line, "Can only mix in traits.".to_string());
+                        return InterpretRtErr!(;"mixin must be a trait");
+                    };
+                    if let Err(message) = class_value.mix_in(&tr, &mut mixed) {
+                        report_runtime(line, message.clone());
+                        return InterpretRtErr!(;message);
+                    }
+                }
                 self.env.borrow_mut().define(name, Some(class_value.into()));
                 Ok(())
             }
+            Stmt::Trait(trait_stmt) => {
+                let name = &trait_stmt.name.lexeme;
+                let mut trait_value = TraitValue::new(name);
+                for function in &trait_stmt.methods {
+                    let fun = UserFn::method(self.env.clone(), function.clone(), name);
+                    trait_value.methods.insert(function.name.lexeme.clone(), fun);
+                }
+                for function in &trait_stmt.class_methods {
+                    let fun = UserFn::method(self.env.clone(), function.clone(), name);
+                    trait_value.class_methods.insert(function.name.lexeme.clone(), fun);
+                }
+                self.env.borrow_mut().define(name, Some(trait_value.into()));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn do_while_runs_body_before_condition() {
+    use crate::test_util::{global, run_source};
+
+    let src = r#"
+var runs = 0;
+do { runs = runs + 1; } while (false);
+var i = 0;
+var inner = "outer";
+do {
+  var inner = i;
+  i = i + 1;
+} while (i < 3);
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "runs"), Scalar::Number(1.0));
+    assert_eq!(global(&evaluator, "i"), Scalar::Number(3.0));
+    // 循环体是独立的块作用域
+    assert_eq!(global(&evaluator, "inner"), Scalar::String("outer".into()));
+}
+
+#[test]
+fn do_while_reports_missing_parts() {
+    use crate::{
+        error::capture_reports,
+        test_util::{parse_source, report_lines},
+    };
+
+    let errors = |src: &str| report_lines(&capture_reports(|| parse_source(src)).1);
+    assert_eq!(
+        errors("do { } (true);"),
+        ["[line 1] Expect 'while' after do body."]
+    );
+    assert_eq!(
+        errors("do { } while true);"),
+        ["[line 1] Expect '(' after 'while'."]
+    );
+    assert_eq!(
+        errors("do { } while (false)"),
+        ["[line 1] Expect ';' after do-while condition."]
+    );
+}
+
+#[test]
+fn for_loops_with_and_without_lowering() {
+    use crate::{
+        optimizer::Optimizer,
+        resolver::Resolver,
+        test_util::{global, parse_source},
+    };
+
+    let src = r#"
+var f1; var f2; var f3;
+for (var i = 1; i < 4; i = i + 1) {
+  var j = i;
+  fun f() { return i * 10 + j; }
+  if (j == 1) f1 = f; else if (j == 2) f2 = f; else f3 = f;
+}
+var c1 = f1(); var c2 = f2(); var c3 = f3();
+
+var n = 0;
+for (; n < 3;) n = n + 1;
+fun first_over(limit) {
+  for (var i = 0; ; i = i + 1) if (i > limit) return i;
+}
+var over = first_over(4);
+fun forever() { for (;;) return "out"; }
+var out = forever();
+fun sum(count) {
+  var total = 0;
+  for (var i = 1; i <= count; i = i + 1) {
+    var step = i;
+    total = total + step;
+  }
+  return total;
+}
+var total = sum(4);
+"#;
+    for lower in [false, true] {
+        let mut stmts = parse_source(src);
+        if lower {
+            Optimizer::lower(&mut stmts);
         }
+        let mut evaluator = Evaluator::new(true);
+        Resolver::new(&mut evaluator).resolve_program(&stmts);
+        stmts
+            .iter()
+            .try_for_each(|stmt| evaluator.eval(stmt))
+            .unwrap();
+        // 循环变量 i 只有一个，循环体里的 j 每次都是新的
+        assert_eq!(global(&evaluator, "c1"), Scalar::Number(41.0));
+        assert_eq!(global(&evaluator, "c2"), Scalar::Number(42.0));
+        assert_eq!(global(&evaluator, "c3"), Scalar::Number(43.0));
+        assert_eq!(global(&evaluator, "n"), Scalar::Number(3.0));
+        assert_eq!(global(&evaluator, "over"), Scalar::Number(5.0));
+        assert_eq!(global(&evaluator, "out"), Scalar::String("out".into()));
+        assert_eq!(global(&evaluator, "total"), Scalar::Number(10.0));
     }
 }
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use crate::{
+    callable::Callable,
+    constants::DEFAULT_MAX_CALL_DEPTH,
     data_types::scaler::Scalar,
     environment::{EnvErr, Environment, EnvironmentType},
     error::report_runtime,
@@ -10,13 +12,22 @@ use crate::{
     InterpretRtErr,
 };
 
-use super::{error::InterpretResult, InterpretError, Interprete};
+use super::{error::InterpretResult, Frame, InterpretError, Interprete, TailCall};
 
 pub struct Evaluator {
-    pub(crate) locals: HashMap<usize, (usize, String)>,
+    // 表达式地址 -> (往外几层作用域, 第几个 slot)
+    pub(crate) locals: HashMap<usize, (usize, usize)>,
     pub(crate) env: EnvironmentType,
     pub(crate) global: EnvironmentType,
     pub(crate) resolver: bool,
+    // 正在执行的用户函数，最里层在最后
+    pub(crate) frames: Vec<Frame>,
+    // 下一个进入的用户函数的调用处行号
+    pub(crate) call_line: usize,
+    // 运行时错误发生时的调用栈
+    pub(crate) backtrace: Option<Vec<Frame>>,
+    // frames 的长度上限
+    pub(crate) max_depth: usize,
 }
 
 impl Default for Evaluator {
@@ -33,17 +44,21 @@ impl Evaluator {
             env: global.clone(),
             global,
             resolver,
+            frames: vec![],
+            call_line: 0,
+            backtrace: None,
+            max_depth: DEFAULT_MAX_CALL_DEPTH,
         }
     }
-    pub(crate) fn resolve(&mut self, expr: &Expr, depth: usize) {
-        self.locals.insert(expr.as_ptr(), (depth, expr.to_string()));
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
     }
-    pub(crate) fn get_depth(&self, expr: &Expr) -> Option<usize> {
-        let ptr = expr.as_ptr();
-        // println!("ptr: {:?} name: {} local: {:?}", ptr, expr.to_string(), self.locals);
-
-        let result = self.locals.get(&ptr).cloned();
-        result.map(|(depth, _)| depth)
+    pub(crate) fn resolve(&mut self, expr: &Expr, depth: usize, slot: usize) {
+        self.locals.insert(expr.as_ptr(), (depth, slot));
+    }
+    pub(crate) fn get_local(&self, expr: &Expr) -> Option<(usize, usize)> {
+        self.locals.get(&expr.as_ptr()).copied()
     }
     pub(crate) fn assign_variable(
         &mut self,
@@ -54,9 +69,8 @@ impl Evaluator {
         if !self.resolver {
             return self.env.borrow_mut().assign(name, value);
         }
-        let distance = self.get_depth(expr);
-        if let Some(distance) = distance {
-            self.env.borrow_mut().assign_at(distance, name, value)
+        if let Some((depth, slot)) = self.get_local(expr) {
+            self.env.borrow_mut().assign_at(depth, slot, value)
         } else {
             self.global.borrow_mut().assign(name, value)
         }
@@ -65,9 +79,8 @@ impl Evaluator {
         if !self.resolver {
             return self.env.borrow().get(name);
         }
-        let distance = self.get_depth(expr);
-        if let Some(distance) = distance {
-            self.env.borrow().get_at(distance, name)
+        if let Some((depth, slot)) = self.get_local(expr) {
+            self.env.borrow().get_at(depth, slot)
         } else {
             self.global.borrow().get(name)
         }
@@ -90,6 +103,61 @@ impl Evaluator {
         self.env = old_env;
         result
     }
+    // 函数体以尾调用结束时，在这里循环调用下一个函数，直到得到真正的返回值
+    pub(crate) fn finish_tail_calls(
+        &mut self,
+        mut result: InterpretResult<Scalar>,
+    ) -> InterpretResult<Scalar> {
+        while let Err(InterpretError::TailCall(tail)) = result {
+            let TailCall {
+                function,
+                args,
+                named,
+                paren,
+            } = *tail;
+            self.call_line = paren.line;
+            result = function.enter(self, args, named, &paren);
+        }
+        result
+    }
+    pub(crate) fn eval_in_env(
+        &mut self,
+        expr: &Expr,
+        env: EnvironmentType,
+    ) -> InterpretResult<Scalar> {
+        let old_env = std::mem::replace(&mut self.env, env);
+        let result = self.eval(expr);
+        self.env = old_env;
+        result
+    }
+    // print、字符串拼接、str()、log() 共用：实例的类定义了 `toString()` 就用它的结果
+    pub(crate) fn stringify(&mut self, value: &Scalar) -> InterpretResult<String> {
+        match value {
+            Scalar::Instance(instance) => {
+                let method = instance.borrow().class.find_method("toString");
+                let Some(method) = method else {
+                    return Ok(value.to_string());
+                };
+                match method.bind(value.clone()).call(self, vec![])? {
+                    Scalar::String(s) => Ok(s),
+                    _ => {
+                        let message = "toString() must return a string.".to_string();
+                        report_runtime(method.line(), message);
+                        InterpretRtErr!(;"bad toString")
+                    }
+                }
+            }
+            Scalar::List(list) => {
+                let items = list.borrow().clone();
+                let items = items
+                    .iter()
+                    .map(|item| self.stringify(item))
+                    .collect::<InterpretResult<Vec<_>>>()?;
+                Ok(format!("[{}]", items.join(", ")))
+            }
+            _ => Ok(value.to_string()),
+        }
+    }
     pub(crate) fn check_number_operands(
         &self,
         left: &Scalar,
@@ -117,3 +185,33 @@ impl Evaluator {
         }
     }
 }
+
+#[test]
+fn to_string_is_used_when_printing_and_concatenating() {
+    use crate::test_util::{global, run_source, runtime_errors};
+
+    let src = r#"
+class P { toString() { return "P!"; } }
+class Q {}
+print P();
+var left = P() + ".";
+var right = "<" + P();
+var converted = str(P());
+var plain = str(Q());
+"#;
+    let (evaluator, result) = run_source(src);
+    result.unwrap();
+    assert_eq!(global(&evaluator, "left"), Scalar::String("P!.".into()));
+    assert_eq!(global(&evaluator, "right"), Scalar::String("<P!".into()));
+    assert_eq!(global(&evaluator, "converted"), Scalar::String("P!".into()));
+    assert_eq!(global(&evaluator, "plain"), Scalar::String("Q instance".into()));
+
+    // 报错的行是 toString 声明的行
+    let class = "class N {\n  toString() { return 1; }\n}\n";
+    for use_it in ["print N();", "N() + \"\";", "\"\" + N();", "str(N());"] {
+        assert_eq!(
+            runtime_errors(&format!("{}{}", class, use_it)),
+            ["[line 2] toString() must return a string."]
+        );
+    }
+}
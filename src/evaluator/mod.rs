@@ -3,8 +3,10 @@ mod evaluator;
 mod eval_expr;
 mod eval_stmt;
 mod error;
+mod call_stack;
 
 
 pub use interface::*;
 pub use evaluator::*;
 pub use error::*;
+pub use call_stack::*;
@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use crate::token::Token;
 
 use super::Expr;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct AssignExpr {
     pub name: Token,
@@ -1,11 +1,24 @@
+use serde::{Deserialize, Serialize};
 use crate::token::Token;
 
 use super::Expr;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CallExpr {
     pub callee: Expr,
     pub arguments: Vec<Expr>,
+    // `f(a, name: b)`，只能跟在位置参数后面
+    #[serde(default)]
+    pub named: Vec<NamedArg>,
     pub parent: Token,
+    // `?.`：对象为 nil 时整条链短路为 nil
+    #[serde(default)]
+    pub optional: bool,
 }
 impl CallExpr {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NamedArg {
+    pub name: Token,
+    pub value: Expr,
+}
@@ -1,9 +1,13 @@
+use serde::{Deserialize, Serialize};
 use crate::token::Token;
 
 use super::Expr;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetExpr {
     pub object: Expr,
     pub name: Token,
+    // `?.`：对象为 nil 时整条链短路为 nil
+    #[serde(default)]
+    pub optional: bool,
 }
\ No newline at end of file
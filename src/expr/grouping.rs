@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use super::Expr;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GroupingExpr {
     pub expression: Expr,
 }
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use crate::token::Token;
+
+use super::Expr;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexExpr {
+    pub object: Expr,
+    pub bracket: Token,
+    pub index: Expr,
+}
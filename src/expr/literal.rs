@@ -1,8 +1,9 @@
-use crate::data_types::scaler::Scalar;
-
+use serde::{Deserialize, Serialize};
 
+use crate::data_types::scaler::Scalar;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "LiteralValue", from = "LiteralValue")]
 pub struct LiteralExpr {
     pub value: Scalar,
 }
@@ -41,3 +42,34 @@ impl From<&str> for LiteralExpr {
         }
     }
 }
+
+// 字面量只会是 bool / number / string / nil，序列化时只保留这几种
+#[derive(Serialize, Deserialize)]
+enum LiteralValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Nil,
+}
+impl From<LiteralExpr> for LiteralValue {
+    fn from(literal: LiteralExpr) -> Self {
+        match literal.value {
+            Scalar::Bool(b) => Self::Bool(b),
+            Scalar::Number(n) => Self::Number(n),
+            Scalar::String(s) => Self::String(s),
+            Scalar::Nil => Self::Nil,
+            other => unreachable!("literal holds a runtime value: {:?}", other),
+        }
+    }
+}
+impl From<LiteralValue> for LiteralExpr {
+    fn from(value: LiteralValue) -> Self {
+        let value = match value {
+            LiteralValue::Bool(b) => Scalar::Bool(b),
+            LiteralValue::Number(n) => Scalar::Number(n),
+            LiteralValue::String(s) => Scalar::String(s),
+            LiteralValue::Nil => Scalar::Nil,
+        };
+        Self { value }
+    }
+}
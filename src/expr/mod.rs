@@ -1,13 +1,17 @@
 use std::ptr::addr_of;
 
+use serde::{Deserialize, Serialize};
+
 use assign::AssignExpr;
 use binary::BinaryExpr;
 use call::CallExpr;
 use get::GetExpr;
 use grouping::GroupingExpr;
+use index::IndexExpr;
 use literal::LiteralExpr;
 use logical::LogicalExpr;
 use set::SetExpr;
+use spread::SpreadExpr;
 use super_expr::SuperExpr;
 use this::ThisExpr;
 use unary::UnaryExpr;
@@ -18,15 +22,17 @@ pub mod binary;
 pub mod call;
 pub mod get;
 pub mod grouping;
+pub mod index;
 pub mod literal;
 pub mod logical;
 pub mod set;
+pub mod spread;
 pub mod super_expr;
 pub mod this;
 pub mod unary;
 pub mod variable;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Expr {
     Variable(Box<VariableExpr>),
     Assign(Box<AssignExpr>),
@@ -37,6 +43,8 @@ pub enum Expr {
     Call(Box<CallExpr>),
     Unary(Box<UnaryExpr>),
     Get(Box<GetExpr>),
+    Index(Box<IndexExpr>),
+    Spread(Box<SpreadExpr>),
     Set(Box<SetExpr>),
     This(Box<ThisExpr>),
     Super(Box<SuperExpr>),
@@ -48,8 +56,27 @@ impl Expr {
             _ => None,
         }
     }
+    // 节点的堆地址，Resolver 用它记录局部变量；取 Box 里面的地址，移动 Expr 不会失效
     pub(crate) fn as_ptr(&self) -> usize {
-        addr_of!(*self) as usize
+        fn addr<T>(node: &T) -> usize {
+            addr_of!(*node) as usize
+        }
+        match self {
+            Self::Variable(e) => addr(e.as_ref()),
+            Self::Assign(e) => addr(e.as_ref()),
+            Self::Binary(e) => addr(e.as_ref()),
+            Self::Logical(e) => addr(e.as_ref()),
+            Self::Grouping(e) => addr(e.as_ref()),
+            Self::Literal(e) => addr(e.as_ref()),
+            Self::Call(e) => addr(e.as_ref()),
+            Self::Unary(e) => addr(e.as_ref()),
+            Self::Get(e) => addr(e.as_ref()),
+            Self::Index(e) => addr(e.as_ref()),
+            Self::Spread(e) => addr(e.as_ref()),
+            Self::Set(e) => addr(e.as_ref()),
+            Self::This(e) => addr(e.as_ref()),
+            Self::Super(e) => addr(e.as_ref()),
+        }
     }
 }
 impl From<BinaryExpr> for Expr {
@@ -97,6 +124,16 @@ impl From<GetExpr> for Expr {
         Self::Get(value.into())
     }
 }
+impl From<IndexExpr> for Expr {
+    fn from(value: IndexExpr) -> Self {
+        Self::Index(value.into())
+    }
+}
+impl From<SpreadExpr> for Expr {
+    fn from(value: SpreadExpr) -> Self {
+        Self::Spread(value.into())
+    }
+}
 impl From<SetExpr> for Expr {
     fn from(value: SetExpr) -> Self {
         Self::Set(value.into())
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use crate::token::Token;
+
+use super::Expr;
+
+// 只会出现在调用参数里：`f(...xs)`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpreadExpr {
+    pub ellipsis: Token,
+    pub expression: Expr,
+}
@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use crate::token::Token;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SuperExpr {
     pub keyword: Token,
     pub method: Token,
@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use crate::token::Token;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 
 pub struct ThisExpr {
     pub keyword: Token,
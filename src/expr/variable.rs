@@ -1,7 +1,8 @@
+use serde::{Deserialize, Serialize};
 use crate::token::Token;
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct VariableExpr {
     pub name: Token,
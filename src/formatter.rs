@@ -0,0 +1,264 @@
+use bytes::Bytes;
+
+use crate::{
+    error::had_error, parser::Parser, scanner::Scanner, token::Token, token_type::TokenType,
+};
+use TokenType::*;
+
+const INDENT: &str = "    ";
+
+// 基于 token 流的格式化：保留注释，最多保留一个空行，其余空白全部重排
+pub struct Formatter {
+    out: String,
+    indent: usize,
+    paren_depth: usize,
+    line_start: bool,
+    pending_newline: bool,
+    after_prefix: bool,
+    // `do {` 打开时的缩进层级，用来把 `} while (...)` 留在同一行
+    do_blocks: Vec<usize>,
+    closed_do: bool,
+    prev: Option<Token>,
+}
+
+impl Formatter {
+    // 源码有扫描或语法错误时返回 None（错误已经报告过）
+    pub fn format_source(source: Bytes) -> Option<String> {
+        let mut scanner = Scanner::with_comments(source);
+        scanner.scan_tokens().expect("scan_tokens");
+        if had_error() {
+            return None;
+        }
+        let tokens = scanner.tokens();
+        let code = tokens
+            .iter()
+            .filter(|t| !matches!(t.t_type, COMMENT(_)))
+            .cloned()
+            .collect();
+        Parser::new(code).parse();
+        if had_error() {
+            return None;
+        }
+        let mut formatter = Self {
+            out: String::new(),
+            indent: 0,
+            paren_depth: 0,
+            line_start: true,
+            pending_newline: false,
+            after_prefix: false,
+            do_blocks: vec![],
+            closed_do: false,
+            prev: None,
+        };
+        for token in tokens.iter().filter(|t| t.t_type != EOF) {
+            formatter.emit(token);
+        }
+        if !formatter.out.is_empty() {
+            formatter.out.push('\n');
+        }
+        Some(formatter.out)
+    }
+
+    fn emit(&mut self, token: &Token) {
+        if token.t_type == RightBrace {
+            self.indent = self.indent.saturating_sub(1);
+            // 空块写成 `{}`
+            self.pending_newline = !matches!(self.prev_type(), Some(LeftBrace));
+            self.closed_do = self.do_blocks.last() == Some(&self.indent);
+            if self.closed_do {
+                self.do_blocks.pop();
+            }
+        }
+        if matches!(token.t_type, COMMENT(_)) {
+            self.emit_comment(token);
+            return;
+        }
+        if self.pending_newline {
+            if self.continues_after_brace(token) {
+                self.out.push(' ');
+            } else {
+                self.newline(token);
+            }
+            self.pending_newline = false;
+        } else if !self.line_start && self.space_before(token) {
+            self.out.push(' ');
+        }
+        if self.line_start {
+            self.out.push_str(&INDENT.repeat(self.indent));
+            self.line_start = false;
+        }
+        self.out.push_str(&token.lexeme);
+
+        self.after_prefix = match token.t_type {
+            BANG | DotDotDot => true,
+            MINUS => !self.prev_is_operand(),
+            _ => false,
+        };
+        match token.t_type {
+            LeftParen => self.paren_depth += 1,
+            RightParen => self.paren_depth = self.paren_depth.saturating_sub(1),
+            LeftBrace => {
+                if matches!(self.prev_type(), Some(DO)) {
+                    self.do_blocks.push(self.indent);
+                }
+                self.indent += 1;
+                self.pending_newline = true;
+            }
+            RightBrace => self.pending_newline = true,
+            SEMICOLON if self.paren_depth == 0 => self.pending_newline = true,
+            _ => {}
+        }
+        self.prev = Some(token.clone());
+    }
+
+    fn emit_comment(&mut self, token: &Token) {
+        let COMMENT(text) = &token.t_type else {
+            unreachable!()
+        };
+        let same_line = self.prev.as_ref().is_some_and(|p| p.line == token.line);
+        if same_line && !self.line_start {
+            // 行尾注释跟在上一个 token 后面
+            self.out.push(' ');
+        } else {
+            if !self.out.is_empty() {
+                self.newline(token);
+            }
+            self.out.push_str(&INDENT.repeat(self.indent));
+        }
+        self.out.push_str(text);
+        self.line_start = false;
+        // 注释后面必须换行，否则会吞掉后面的代码
+        self.pending_newline = true;
+        self.after_prefix = false;
+        self.prev = Some(token.clone());
+    }
+
+    fn newline(&mut self, next: &Token) {
+        if !self.line_start {
+            self.out.push('\n');
+        }
+        let gap = self
+            .prev
+            .as_ref()
+            .is_some_and(|prev| start_line(next) > prev.line + 1);
+        if gap {
+            self.out.push('\n');
+        }
+        self.line_start = true;
+    }
+
+    // `} else`、`});` 之类不换行
+    fn continues_after_brace(&self, token: &Token) -> bool {
+        matches!(self.prev_type(), Some(RightBrace))
+            && (matches!(token.t_type, ELSE | SEMICOLON | RightParen | COMMA)
+                || (token.t_type == WHILE && self.closed_do))
+    }
+
+    fn space_before(&self, token: &Token) -> bool {
+        let Some(prev) = self.prev_type() else {
+            return false;
+        };
+        if matches!(
+            token.t_type,
+            SEMICOLON | COMMA | COLON | RightParen | RightBracket | DOT | QuestionDot
+        ) {
+            return false;
+        }
+        if matches!(prev, LeftParen | LeftBracket | DOT | QuestionDot) || self.after_prefix {
+            return false;
+        }
+        if token.t_type == RightBrace {
+            return prev != LeftBrace;
+        }
+        if token.t_type == LeftParen {
+            // 调用不加空格，`if (`、`while (` 之类加空格
+            return !matches!(prev, IDENTIFIER(_) | RightParen);
+        }
+        if token.t_type == LeftBracket {
+            return !self.prev_is_operand();
+        }
+        true
+    }
+
+    // 前面是操作数时 `-` 才是二元减号
+    fn prev_is_operand(&self) -> bool {
+        matches!(
+            self.prev_type(),
+            Some(
+                IDENTIFIER(_)
+                    | NUMBER(_)
+                    | STRING(_)
+                    | RightParen
+                    | RightBracket
+                    | TRUE
+                    | FALSE
+                    | NIL
+                    | THIS
+            )
+        )
+    }
+
+    fn prev_type(&self) -> Option<TokenType> {
+        self.prev.as_ref().map(|t| t.t_type.clone())
+    }
+}
+
+// token.line 记录的是 token 结束的行，多行字符串需要往回算
+fn start_line(token: &Token) -> usize {
+    token.line - token.lexeme.matches('\n').count()
+}
+
+#[test]
+fn format_is_idempotent() {
+    let src = Bytes::from(
+        r#"// header
+var a=1;var b = -a*(2+3);   // trailing
+class Foo<Bar{init(x){this.x=x;}
+
+
+  get(){return super.get()or !nil;}}
+for(var i=0;i<3;i=i+1){if(i==1)print"one";else{print i-1;}}
+fun f(){}
+do{i=i-1;}while(i>0);
+print a?.b?.(1)??2;
+fun g(a,...rest){return f(...rest)[ 0 ]-1;}
+fun h(a,b=a*2){return g(b:a,a:b);}
+"#,
+    );
+    let once = Formatter::format_source(src).unwrap();
+    let twice = Formatter::format_source(Bytes::from(once.clone())).unwrap();
+    assert_eq!(once, twice);
+    assert_eq!(
+        once,
+        r#"// header
+var a = 1;
+var b = -a * (2 + 3); // trailing
+class Foo < Bar {
+    init(x) {
+        this.x = x;
+    }
+
+    get() {
+        return super.get() or !nil;
+    }
+}
+for (var i = 0; i < 3; i = i + 1) {
+    if (i == 1) print "one";
+    else {
+        print i - 1;
+    }
+}
+fun f() {}
+do {
+    i = i - 1;
+} while (i > 0);
+print a?.b?.(1) ?? 2;
+fun g(a, ...rest) {
+    return f(...rest)[0] - 1;
+}
+fun h(a, b = a * 2) {
+    return g(b: a, a: b);
+}
+"#
+    );
+}
@@ -6,6 +6,10 @@ pub mod scanner;
 pub mod parser;
 pub mod evaluator;
 pub mod resolver;
+pub mod checker;
+pub mod linter;
+pub mod optimizer;
+pub mod lsp;
 pub mod expr;
 pub mod stmt;
 pub mod token;
@@ -13,9 +17,13 @@ pub mod token_display;
 pub mod token_type;
 pub mod data_types;
 pub mod ast_printer;
+pub mod ast_json;
+pub mod formatter;
 pub mod ast_interpreter;
 pub mod environment;
 pub mod callable;
+#[cfg(test)]
+mod test_util;
 
 trait Visit<T = ()> {
     fn visit_aa(&self, a: &Aa) -> T;
@@ -0,0 +1,252 @@
+use crate::{
+    data_types::scaler::Scalar,
+    expr::Expr,
+    stmt::{function::FunctionStmt, Stmt},
+    token::Token,
+    token_type::TokenType,
+};
+
+use super::{Linter, Rule};
+
+impl Linter {
+    pub(super) fn lint_stmts(&mut self, stmts: &[Stmt]) {
+        let mut returned = None;
+        for stmt in stmts {
+            if let Some(line) = returned.take() {
+                let line = stmt_line(stmt).unwrap_or(line);
+                self.report(Rule::UnreachableCode, line, "Unreachable code.".to_string());
+            }
+            if let Stmt::Return(ret) = stmt {
+                returned = Some(ret.keyword.line);
+            }
+            self.lint_stmt(stmt);
+        }
+    }
+
+    fn lint_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Var(var) => {
+                if let Some(initializer) = &var.initializer {
+                    self.lint_expr(initializer);
+                }
+                self.declare(&var.name);
+            }
+            Stmt::Expression(expr) => self.lint_expr(&expr.expression),
+            Stmt::Print(print) => self.lint_expr(&print.expression),
+            Stmt::Block(block) => {
+                if block.statements.is_empty() {
+                    self.report(Rule::EmptyBlock, block.line, "Empty block.".to_string());
+                }
+                self.begin_scope();
+                self.lint_stmts(&block.statements);
+                self.end_scope();
+            }
+            Stmt::If(if_stmt) => {
+                self.lint_condition(&if_stmt.condition);
+                self.lint_stmt(&if_stmt.then_branch);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.lint_stmt(else_branch);
+                }
+            }
+            Stmt::While(while_stmt) => {
+                if is_always_true(&while_stmt.condition) {
+                    let message = "Condition of 'while' is always true.".to_string();
+                    self.report(Rule::ConstantCondition, while_stmt.line, message);
+                }
+                self.lint_condition(&while_stmt.condition);
+                self.lint_stmt(&while_stmt.body);
+            }
+            Stmt::DoWhile(do_while) => {
+                self.lint_stmt(&do_while.body);
+                if is_always_true(&do_while.condition) {
+                    let message = "Condition of 'while' is always true.".to_string();
+                    self.report(Rule::ConstantCondition, do_while.keyword.line, message);
+                }
+                self.lint_condition(&do_while.condition);
+            }
+            Stmt::For(for_stmt) => {
+                self.begin_scope();
+                if let Some(initializer) = &for_stmt.initializer {
+                    self.lint_stmt(initializer);
+                }
+                if let Some(condition) = &for_stmt.condition {
+                    self.lint_condition(condition);
+                }
+                if let Some(increment) = &for_stmt.increment {
+                    self.lint_expr(increment);
+                }
+                self.lint_stmt(&for_stmt.body);
+                self.end_scope();
+            }
+            Stmt::Function(function) => {
+                self.declare(&function.name);
+                self.lint_function(function);
+            }
+            Stmt::Return(ret) => {
+                if let Some(value) = &ret.value {
+                    self.lint_expr(value);
+                }
+            }
+            Stmt::Class(class) => {
+                self.declare(&class.name);
+                for method in class.methods.iter().chain(&class.class_methods) {
+                    self.lint_function(method);
+                }
+            }
+            Stmt::Trait(trait_stmt) => {
+                self.declare(&trait_stmt.name);
+                for method in trait_stmt.methods.iter().chain(&trait_stmt.class_methods) {
+                    self.lint_function(method);
+                }
+            }
+        }
+    }
+
+    // 参数和函数体在同一个作用域，和 Resolver 一致
+    fn lint_function(&mut self, function: &FunctionStmt) {
+        self.begin_scope();
+        for param in &function.params {
+            if let Some(default) = &param.default {
+                self.lint_expr(default);
+            }
+            self.declare(&param.name);
+        }
+        if let Some(rest) = &function.rest {
+            self.declare(rest);
+        }
+        self.lint_stmts(&function.fn_body);
+        self.end_scope();
+    }
+
+    fn lint_condition(&mut self, condition: &Expr) {
+        if let Some(assign) = find_assign(condition) {
+            let message = format!(
+                "Assignment to '{}' in a condition, did you mean '=='?",
+                assign.lexeme
+            );
+            self.report(Rule::AssignInCondition, assign.line, message);
+        }
+        self.lint_expr(condition);
+    }
+
+    fn lint_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary(binary) => {
+                use TokenType::*;
+                let is_compare = matches!(
+                    binary.operator.t_type,
+                    EqualEqual | BangEqual | LESS | LessEqual | GREATER | GreaterEqual
+                );
+                if is_compare && same_place(&binary.left, &binary.right) {
+                    let message = format!(
+                        "Comparing a value to itself with '{}'.",
+                        binary.operator.lexeme
+                    );
+                    self.report(Rule::SelfCompare, binary.operator.line, message);
+                }
+                self.lint_expr(&binary.left);
+                self.lint_expr(&binary.right);
+            }
+            Expr::Logical(logical) => {
+                self.lint_expr(&logical.left);
+                self.lint_expr(&logical.right);
+            }
+            Expr::Assign(assign) => self.lint_expr(&assign.value),
+            Expr::Grouping(grouping) => self.lint_expr(&grouping.expression),
+            Expr::Unary(unary) => self.lint_expr(&unary.right),
+            Expr::Call(call) => {
+                self.lint_expr(&call.callee);
+                for arg in &call.arguments {
+                    self.lint_expr(arg);
+                }
+                for arg in &call.named {
+                    self.lint_expr(&arg.value);
+                }
+            }
+            Expr::Get(get) => self.lint_expr(&get.object),
+            Expr::Set(set) => {
+                self.lint_expr(&set.object);
+                self.lint_expr(&set.value);
+            }
+            Expr::Index(index) => {
+                self.lint_expr(&index.object);
+                self.lint_expr(&index.index);
+            }
+            Expr::Spread(spread) => self.lint_expr(&spread.expression),
+            Expr::Variable(_) | Expr::Literal(_) | Expr::This(_) | Expr::Super(_) => {}
+        }
+    }
+}
+
+// 条件顶层（括号、`and`/`or`、`!` 里面）的赋值
+fn find_assign(condition: &Expr) -> Option<&Token> {
+    match condition {
+        Expr::Assign(assign) => Some(&assign.name),
+        Expr::Grouping(grouping) => find_assign(&grouping.expression),
+        Expr::Logical(logical) => {
+            find_assign(&logical.left).or_else(|| find_assign(&logical.right))
+        }
+        Expr::Unary(unary) => find_assign(&unary.right),
+        _ => None,
+    }
+}
+
+// 字面量 true、数字、字符串都是真值
+fn is_always_true(condition: &Expr) -> bool {
+    match condition {
+        Expr::Literal(literal) => !matches!(literal.value, Scalar::Nil | Scalar::Bool(false)),
+        Expr::Grouping(grouping) => is_always_true(&grouping.expression),
+        _ => false,
+    }
+}
+
+// 两边是同一个变量或同一条属性链，比如 `a == a`、`this.x < this.x`
+fn same_place(left: &Expr, right: &Expr) -> bool {
+    match (left, right) {
+        (Expr::Variable(l), Expr::Variable(r)) => l.name.lexeme == r.name.lexeme,
+        (Expr::This(_), Expr::This(_)) => true,
+        (Expr::Get(l), Expr::Get(r)) => {
+            l.name.lexeme == r.name.lexeme && same_place(&l.object, &r.object)
+        }
+        (Expr::Grouping(l), _) => same_place(&l.expression, right),
+        (_, Expr::Grouping(r)) => same_place(left, &r.expression),
+        _ => false,
+    }
+}
+
+fn stmt_line(stmt: &Stmt) -> Option<usize> {
+    match stmt {
+        Stmt::Var(var) => Some(var.name.line),
+        Stmt::Expression(expr) => expr_line(&expr.expression),
+        Stmt::Print(print) => expr_line(&print.expression),
+        Stmt::Block(block) => Some(block.line).filter(|line| *line > 0),
+        Stmt::If(if_stmt) => expr_line(&if_stmt.condition),
+        Stmt::While(while_stmt) => Some(while_stmt.line).filter(|line| *line > 0),
+        Stmt::For(for_stmt) => Some(for_stmt.keyword.line),
+        Stmt::DoWhile(do_while) => Some(do_while.keyword.line),
+        Stmt::Function(function) => Some(function.name.line),
+        Stmt::Return(ret) => Some(ret.keyword.line),
+        Stmt::Class(class) => Some(class.name.line),
+        Stmt::Trait(trait_stmt) => Some(trait_stmt.name.line),
+    }
+}
+
+// 字面量没有位置信息
+fn expr_line(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Variable(variable) => Some(variable.name.line),
+        Expr::Assign(assign) => Some(assign.name.line),
+        Expr::Binary(binary) => expr_line(&binary.left).or(Some(binary.operator.line)),
+        Expr::Logical(logical) => expr_line(&logical.left).or(Some(logical.operator.line)),
+        Expr::Grouping(grouping) => expr_line(&grouping.expression),
+        Expr::Literal(_) => None,
+        Expr::Call(call) => expr_line(&call.callee).or(Some(call.parent.line)),
+        Expr::Unary(unary) => Some(unary.operator.line),
+        Expr::Get(get) => expr_line(&get.object).or(Some(get.name.line)),
+        Expr::Index(index) => expr_line(&index.object).or(Some(index.bracket.line)),
+        Expr::Spread(spread) => Some(spread.ellipsis.line),
+        Expr::Set(set) => expr_line(&set.object).or(Some(set.name.line)),
+        Expr::This(this) => Some(this.keyword.line),
+        Expr::Super(super_expr) => Some(super_expr.keyword.line),
+    }
+}
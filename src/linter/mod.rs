@@ -0,0 +1,262 @@
+mod impls;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{stmt::Stmt, token::Token};
+
+// `lint` 的规则，名字同时用于配置文件、命令行和输出
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    // 局部变量或参数遮蔽了外层的同名变量
+    Shadowing,
+    // `return` 之后的语句
+    UnreachableCode,
+    // `while` 的条件是恒真的字面量
+    ConstantCondition,
+    // 拿一个变量和它自己比较
+    SelfCompare,
+    // 条件里写了赋值，多半想写 `==`
+    AssignInCondition,
+    // 空的 `{}` 块
+    EmptyBlock,
+}
+impl Rule {
+    pub const ALL: [Rule; 6] = [
+        Rule::Shadowing,
+        Rule::UnreachableCode,
+        Rule::ConstantCondition,
+        Rule::SelfCompare,
+        Rule::AssignInCondition,
+        Rule::EmptyBlock,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Shadowing => "shadowing",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::SelfCompare => "self-compare",
+            Rule::AssignInCondition => "assign-in-condition",
+            Rule::EmptyBlock => "empty-block",
+        }
+    }
+}
+
+// 开启的规则。默认全开，配置文件里写 `"rule-name": false` 关闭
+#[derive(Clone, Debug)]
+pub struct LintConfig {
+    enabled: HashSet<Rule>,
+}
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Rule::ALL.into_iter().collect(),
+        }
+    }
+}
+impl LintConfig {
+    // 配置文件是 JSON 对象，例如 `{ "shadowing": false, "empty-block": true }`
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Can't read lint config {}: {}", path.display(), e))?;
+        let rules: HashMap<Rule, bool> = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid lint config {}: {}", path.display(), e))?;
+        let mut config = Self::default();
+        for (rule, on) in rules {
+            config.set(rule, on);
+        }
+        Ok(config)
+    }
+    pub fn set(&mut self, rule: Rule, on: bool) {
+        if on {
+            self.enabled.insert(rule);
+        } else {
+            self.enabled.remove(&rule);
+        }
+    }
+    // 命令行的 `--enable`/`--disable` 覆盖配置文件，同一条规则两边都写时关闭
+    pub fn with_overrides(mut self, enable: &[Rule], disable: &[Rule]) -> Self {
+        for &rule in enable {
+            self.set(rule, true);
+        }
+        for &rule in disable {
+            self.set(rule, false);
+        }
+        self
+    }
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.enabled.contains(&rule)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub line: usize,
+    pub message: String,
+}
+
+// 在 resolve 过的 AST 上找可疑写法，只报告，不影响执行
+pub struct Linter {
+    config: LintConfig,
+    // 作用域里声明过的名字，第一个是全局作用域
+    scopes: Vec<HashSet<String>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    pub fn new(config: LintConfig) -> Self {
+        Self {
+            config,
+            scopes: vec![HashSet::new()],
+            diagnostics: vec![],
+        }
+    }
+
+    // 按行号排序返回
+    pub fn lint(mut self, stmts: &[Stmt]) -> Vec<Diagnostic> {
+        self.lint_stmts(stmts);
+        self.diagnostics.sort_by_key(|d| d.line);
+        self.diagnostics
+    }
+
+    fn report(&mut self, rule: Rule, line: usize, message: String) {
+        if self.config.is_enabled(rule) {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                line,
+                message,
+            });
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashSet::new());
+    }
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+    fn declare(&mut self, name: &Token) {
+        let (_, outer) = self.scopes.split_last().unwrap();
+        // 全局作用域里重复声明不算遮蔽
+        if !outer.is_empty() && outer.iter().any(|scope| scope.contains(&name.lexeme)) {
+            let message = format!(
+                "'{}' shadows a variable from an enclosing scope.",
+                name.lexeme
+            );
+            self.report(Rule::Shadowing, name.line, message);
+        }
+        self.scopes.last_mut().unwrap().insert(name.lexeme.clone());
+    }
+}
+
+pub fn print_human(diagnostics: &[Diagnostic]) {
+    for d in diagnostics {
+        println!("[line {}] Lint ({}): {}", d.line, d.rule.name(), d.message);
+    }
+}
+
+pub fn print_json(diagnostics: &[Diagnostic]) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(diagnostics).expect("serialize diagnostics")
+    );
+}
+
+#[cfg(test)]
+fn lint_source(src: &str, config: LintConfig) -> Vec<String> {
+    let stmts = crate::test_util::parse_source(src);
+    let diagnostics = Linter::new(config).lint(&stmts);
+    diagnostics
+        .iter()
+        .map(|d| format!("[line {}] {}: {}", d.line, d.rule.name(), d.message))
+        .collect()
+}
+
+#[test]
+fn each_rule_reports_suspicious_code() {
+    let src = r#"
+var a = 1;
+fun f(x) {
+  var a = x;
+  return a;
+  print a;
+}
+while (true) {}
+if (a == a) print a;
+if (a = 2) print a;
+"#;
+    assert_eq!(
+        lint_source(src, LintConfig::default()),
+        [
+            "[line 4] shadowing: 'a' shadows a variable from an enclosing scope.",
+            "[line 6] unreachable-code: Unreachable code.",
+            "[line 8] constant-condition: Condition of 'while' is always true.",
+            "[line 8] empty-block: Empty block.",
+            "[line 9] self-compare: Comparing a value to itself with '=='.",
+            "[line 10] assign-in-condition: Assignment to 'a' in a condition, did you mean '=='?",
+        ]
+    );
+}
+
+#[test]
+fn clean_code_has_no_reports() {
+    // 每条规则对应一个不该报的写法
+    let src = r#"
+var a = 1;
+var a = 2;
+fun f(x) {
+  var b = x;
+  if (b > 0) return b;
+  return -b;
+}
+var i = 0;
+while (i < 3) { i = i + 1; }
+if (a == i) print a;
+if ((a == 2)) print a;
+"#;
+    assert!(lint_source(src, LintConfig::default()).is_empty());
+}
+
+#[test]
+fn rules_can_be_turned_off() {
+    let src = "var a = 1;\n{ var a = 2; }\n{}\n";
+    let path = std::env::temp_dir().join(format!("lox-lint-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "shadowing": false, "self-compare": true }"#).unwrap();
+    let config = LintConfig::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    let config = config.unwrap();
+    assert!(!config.is_enabled(Rule::Shadowing));
+    assert_eq!(lint_source(src, config.clone()), ["[line 3] empty-block: Empty block."]);
+
+    // 命令行覆盖配置文件
+    let config = config.with_overrides(&[Rule::Shadowing], &[Rule::EmptyBlock]);
+    assert_eq!(
+        lint_source(src, config),
+        ["[line 2] shadowing: 'a' shadows a variable from an enclosing scope."]
+    );
+
+    let missing = LintConfig::from_file(Path::new("/nonexistent/lint.json"));
+    assert!(missing.unwrap_err().starts_with("Can't read lint config"));
+    let path = std::env::temp_dir().join(format!("lox-lint-bad-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "no-such-rule": true }"#).unwrap();
+    let invalid = LintConfig::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(invalid.unwrap_err().starts_with("Invalid lint config"));
+}
+
+#[test]
+fn json_output_uses_rule_names() {
+    let stmts = crate::test_util::parse_source("{}");
+    let diagnostics = Linter::new(LintConfig::default()).lint(&stmts);
+    let json = serde_json::to_value(&diagnostics).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([{ "rule": "empty-block", "line": 1, "message": "Empty block." }])
+    );
+}
@@ -3,40 +3,172 @@ use std::path::PathBuf;
 use bytes::Bytes;
 
 use crate::{
-    error::{had_error, MyResult, HAD_ERROR},
-    evaluator::{Evaluator, InterpretError, Interprete},
+    ast_json::{from_json, to_json},
+    checker::Checker,
+    command::{AstFormat, LintFormat},
+    error::{had_error, my_error, MyResult, HAD_ERROR},
+    evaluator::{print_backtrace, Evaluator, InterpretError, Interprete},
     expr::Expr,
+    formatter::Formatter,
+    linter::{print_human, print_json, LintConfig, Linter, Rule},
+    lsp::Server,
+    optimizer::Optimizer,
     parser::Parser,
     resolver::Resolver,
     scanner::Scanner,
+    stmt::Stmt,
     MyErr,
 };
 
 pub struct Lox {}
 impl Lox {
-    pub fn run_file(path: PathBuf, resolver: bool) -> MyResult<()> {
-        let scanner = Self::tokenize(path);
-        let mut parser = Parser::new(scanner.tokens());
-        let stmts = parser.parse();
+    pub fn run_file(
+        path: PathBuf,
+        resolver: bool,
+        optimize: bool,
+        max_depth: usize,
+    ) -> MyResult<()> {
+        let stmts = Self::parse_stmts(path);
         if had_error() {
             return MyErr!(;"HAD_ERROR");
         }
-        let mut evaluator = Evaluator::new(resolver);
+        Self::run_stmts(stmts, resolver, optimize, max_depth)
+    }
+    pub fn run_ast_file(
+        path: PathBuf,
+        resolver: bool,
+        optimize: bool,
+        max_depth: usize,
+    ) -> MyResult<()> {
+        let b = Self::read(path);
+        let stmts = match from_json(&b) {
+            Ok(stmts) => stmts,
+            Err(e) => {
+                my_error(e.line(), format!("Invalid AST: {}", e));
+                return MyErr!(;"HAD_ERROR");
+            }
+        };
+        Self::run_stmts(stmts, resolver, optimize, max_depth)
+    }
+    pub fn ast(path: PathBuf, format: AstFormat) {
+        let stmts = Self::parse_stmts(path);
+        if had_error() {
+            return;
+        }
+        match format {
+            AstFormat::Json => println!("{}", to_json(&stmts).expect("serialize ast")),
+        }
+    }
+    // 解析、resolve 之后做类型检查，不执行；错误通过 HAD_ERROR 反映到退出码
+    pub fn check(path: PathBuf) {
+        let stmts = Self::parse_stmts(path);
+        if had_error() {
+            return;
+        }
+        let mut evaluator = Evaluator::new(true);
+        Resolver::new(&mut evaluator).resolve_program(&stmts);
+        if had_error() {
+            return;
+        }
+        Checker::new().check(&stmts);
+    }
+    // 返回 false 表示有 lint 报告；配置文件先生效，命令行的 --enable/--disable 覆盖它
+    pub fn lint(
+        path: PathBuf,
+        config: Option<PathBuf>,
+        enable: Vec<Rule>,
+        disable: Vec<Rule>,
+        format: LintFormat,
+    ) -> bool {
+        let lint_config = match config {
+            Some(config) => match LintConfig::from_file(&config) {
+                Ok(lint_config) => lint_config,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return false;
+                }
+            },
+            None => LintConfig::default(),
+        };
+        let lint_config = lint_config.with_overrides(&enable, &disable);
+        let stmts = Self::parse_stmts(path);
+        if had_error() {
+            return true;
+        }
+        let mut evaluator = Evaluator::new(true);
+        Resolver::new(&mut evaluator).resolve_program(&stmts);
+        if had_error() {
+            return true;
+        }
+        let diagnostics = Linter::new(lint_config).lint(&stmts);
+        match format {
+            LintFormat::Human => print_human(&diagnostics),
+            LintFormat::Json => print_json(&diagnostics),
+        }
+        diagnostics.is_empty()
+    }
+    // 返回 false 表示 `--check` 下有文件没有格式化
+    pub fn fmt(files: Vec<PathBuf>, check: bool) -> bool {
+        let mut formatted = true;
+        for path in files {
+            let source = Self::read(path.clone());
+            let Some(output) = Formatter::format_source(source.clone()) else {
+                continue;
+            };
+            if output.as_bytes() == source.as_ref() {
+                continue;
+            }
+            if check {
+                eprintln!("Would reformat: {}", path.display());
+                formatted = false;
+            } else {
+                std::fs::write(&path, output).expect("write file");
+            }
+        }
+        formatted
+    }
+    // 客户端先发 `shutdown` 再发 `exit` 时返回 true
+    pub fn lsp() -> bool {
+        let stdin = std::io::stdin();
+        Server::new()
+            .run(stdin.lock(), std::io::stdout().lock())
+            .unwrap_or_else(|e| {
+                eprintln!("lsp: {}", e);
+                false
+            })
+    }
+    fn run_stmts(
+        mut stmts: Vec<Stmt>,
+        resolver: bool,
+        optimize: bool,
+        max_depth: usize,
+    ) -> MyResult<()> {
+        let mut evaluator = Evaluator::new(resolver).with_max_depth(max_depth);
+        if optimize {
+            Optimizer::lower(&mut stmts);
+        }
         if resolver {
             let mut resolver = Resolver::new(&mut evaluator);
-            resolver.resolve_stmts(&stmts);
+            resolver.resolve_program(&stmts);
             if had_error() {
                 return MyErr!(;"HAD_ERROR");
             }
         }
+        if optimize {
+            Optimizer::optimize(&mut stmts);
+        }
         for stmt in stmts {
             let res = evaluator.eval(&stmt);
             if let Err(e) = res {
+                if matches!(e, InterpretError::Runtime(_) | InterpretError::Other) {
+                    print_backtrace(&evaluator.take_backtrace());
+                }
                 match e {
                     InterpretError::Runtime(msg) => {
                         eprintln!("[runtime err] {}", msg);
                         return MyErr!(;msg);
                     }
+                    InterpretError::Other => return MyErr!(;"HAD_RUNTIME_ERROR"),
                     _ => {
                         eprintln!("[stmt err] {:?}", e)
                     }
@@ -45,6 +177,11 @@ impl Lox {
         }
         Ok(())
     }
+    fn parse_stmts(path: PathBuf) -> Vec<Stmt> {
+        let scanner = Self::tokenize(path);
+        let mut parser = Parser::new(scanner.tokens());
+        parser.parse()
+    }
     pub fn parse(path: PathBuf) -> Option<Expr> {
         let scanner = Self::tokenize(path);
         let mut parser = Parser::new(scanner.tokens());
@@ -0,0 +1,308 @@
+use bytes::Bytes;
+use serde_json::{json, Value};
+
+use crate::{
+    environment::Environment,
+    error::{capture_reports, Report},
+    evaluator::Evaluator,
+    parser::Parser,
+    resolver::{BindingKind, Position, Resolver, SymbolIndex},
+    scanner::Scanner,
+    stmt::{function::FunctionStmt, Stmt},
+    token::Token,
+};
+
+// 一个文档解析、resolve 之后的结果，文档每次变化都重新算一遍
+pub struct Analysis {
+    stmts: Vec<Stmt>,
+    reports: Vec<Report>,
+    index: SymbolIndex,
+    lines: Lines,
+}
+
+impl Analysis {
+    pub fn new(text: &str) -> Self {
+        let ((stmts, index), reports) = capture_reports(|| {
+            let mut scanner = Scanner::new(Bytes::from(text.to_string()));
+            // 扫描失败时已经报告过，用扫到的部分继续
+            let _ = scanner.scan_tokens();
+            let stmts = Parser::new(scanner.tokens()).parse();
+            // 有语法错误也 resolve 解析出来的部分，编辑到一半时跳转还能用
+            let mut evaluator = Evaluator::new(true);
+            let mut resolver = Resolver::new(&mut evaluator).with_index();
+            resolver.resolve_program(&stmts);
+            (stmts, resolver.take_index().unwrap_or_default())
+        });
+        Self {
+            stmts,
+            reports,
+            index,
+            lines: Lines(text.split('\n').map(str::to_string).collect()),
+        }
+    }
+
+    pub fn diagnostics(&self) -> Value {
+        let diagnostics: Vec<_> = self
+            .reports
+            .iter()
+            .map(|report| {
+                let range = match &report.token {
+                    Some(token) => self.lines.range(token),
+                    None => line_range(report.line),
+                };
+                json!({
+                    "range": range,
+                    "severity": if report.warning { 2 } else { 1 },
+                    "source": "lox",
+                    "message": report.message,
+                })
+            })
+            .collect();
+        json!(diagnostics)
+    }
+
+    pub fn definition(&self, uri: &str, position: Position) -> Value {
+        let position = self.lines.byte_position(position);
+        match self.index.definition_at(position) {
+            Some(i) => self.lines.location(uri, &self.index.definitions[i].name),
+            None => Value::Null,
+        }
+    }
+
+    pub fn references(&self, uri: &str, position: Position, include_declaration: bool) -> Value {
+        let position = self.lines.byte_position(position);
+        let Some(i) = self.index.definition_at(position) else {
+            return json!([]);
+        };
+        let tokens = self.index.references_to(i);
+        // 第一个是声明本身
+        let skip = usize::from(!include_declaration);
+        let locations: Vec<_> = tokens
+            .into_iter()
+            .skip(skip)
+            .map(|token| self.lines.location(uri, token))
+            .collect();
+        json!(locations)
+    }
+
+    pub fn hover(&self, position: Position) -> Value {
+        let position = self.lines.byte_position(position);
+        let Some(i) = self.index.definition_at(position) else {
+            return Value::Null;
+        };
+        let definition = &self.index.definitions[i];
+        let kind = match (definition.kind, definition.global) {
+            (BindingKind::Variable, true) => "global variable",
+            (BindingKind::Variable, false) => "local variable",
+            (BindingKind::Parameter, _) => "parameter",
+            (BindingKind::Function, true) => "function",
+            (BindingKind::Function, false) => "local function",
+            (BindingKind::Class, true) => "class",
+            (BindingKind::Class, false) => "local class",
+            (BindingKind::Implicit, _) => return Value::Null,
+        };
+        json!({
+            "contents": {
+                "kind": "plaintext",
+                "value": format!(
+                    "({}) {}, declared on line {}",
+                    kind, definition.name.lexeme, definition.name.line
+                ),
+            },
+        })
+    }
+
+    pub fn document_symbols(&self) -> Value {
+        json!(symbols(&self.lines, &self.stmts))
+    }
+
+    pub fn completion(&self, position: Position) -> Value {
+        let position = self.lines.byte_position(position);
+        let mut names: Vec<(String, u8)> = Environment::global_env()
+            .borrow()
+            .names()
+            .into_iter()
+            .map(|name| (name, COMPLETION_FUNCTION))
+            .collect();
+        for definition in self.index.visible_at(position) {
+            let kind = match definition.kind {
+                BindingKind::Function => COMPLETION_FUNCTION,
+                BindingKind::Class => COMPLETION_CLASS,
+                _ => COMPLETION_VARIABLE,
+            };
+            names.push((definition.name.lexeme.clone(), kind));
+        }
+        names.sort();
+        names.dedup_by(|a, b| a.0 == b.0);
+        let items: Vec<_> = names
+            .into_iter()
+            .map(|(label, kind)| json!({ "label": label, "kind": kind }))
+            .collect();
+        json!(items)
+    }
+}
+
+// LSP 的 CompletionItemKind 和 SymbolKind
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_CLASS: u8 = 7;
+const SYMBOL_CLASS: u8 = 5;
+const SYMBOL_METHOD: u8 = 6;
+const SYMBOL_INTERFACE: u8 = 11;
+const SYMBOL_FUNCTION: u8 = 12;
+
+// 类、trait 和它们的方法，以及函数（包括函数里面声明的函数）
+fn symbols(lines: &Lines, stmts: &[Stmt]) -> Vec<Value> {
+    let method_symbols = |methods: &[std::rc::Rc<FunctionStmt>]| -> Vec<Value> {
+        methods
+            .iter()
+            .map(|method| symbol(lines, &method.name, SYMBOL_METHOD, vec![]))
+            .collect()
+    };
+    stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Class(class) => {
+                let mut children = method_symbols(&class.class_methods);
+                children.extend(method_symbols(&class.methods));
+                Some(symbol(lines, &class.name, SYMBOL_CLASS, children))
+            }
+            Stmt::Trait(trait_stmt) => {
+                let mut children = method_symbols(&trait_stmt.class_methods);
+                children.extend(method_symbols(&trait_stmt.methods));
+                Some(symbol(lines, &trait_stmt.name, SYMBOL_INTERFACE, children))
+            }
+            Stmt::Function(function) => Some(symbol(
+                lines,
+                &function.name,
+                SYMBOL_FUNCTION,
+                symbols(lines, &function.fn_body),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+// 没有语句的结束位置，范围只覆盖名字
+fn symbol(lines: &Lines, name: &Token, kind: u8, children: Vec<Value>) -> Value {
+    json!({
+        "name": name.lexeme,
+        "kind": kind,
+        "range": lines.range(name),
+        "selectionRange": lines.range(name),
+        "children": children,
+    })
+}
+
+// 文档的每一行，用来换算列：Token 的列按字节算，LSP 的列按 UTF-16 算
+struct Lines(Vec<String>);
+
+impl Lines {
+    fn line(&self, line: usize) -> &str {
+        self.0
+            .get(line.saturating_sub(1))
+            .map_or("", String::as_str)
+    }
+    fn utf16_column(&self, line: usize, column: usize) -> usize {
+        let text = self.line(line);
+        match text.get(..column) {
+            Some(prefix) => prefix.encode_utf16().count(),
+            None => column,
+        }
+    }
+    // 客户端发来的 UTF-16 列换成字节列，超出行尾的部分原样保留
+    fn byte_position(&self, (line, character): Position) -> Position {
+        let mut units = 0;
+        for (i, ch) in self.line(line).char_indices() {
+            if units >= character {
+                return (line, i);
+            }
+            units += ch.len_utf16();
+        }
+        (
+            line,
+            self.line(line).len() + character.saturating_sub(units),
+        )
+    }
+    // Token 的行从 1 开始，LSP 从 0 开始
+    fn range(&self, token: &Token) -> Value {
+        let line = token.line.saturating_sub(1);
+        let start = self.utf16_column(token.line, token.column);
+        let end = self.utf16_column(token.line, token.column + token.lexeme.len());
+        json!({
+            "start": { "line": line, "character": start },
+            "end": { "line": line, "character": end },
+        })
+    }
+    fn location(&self, uri: &str, token: &Token) -> Value {
+        json!({ "uri": uri, "range": self.range(token) })
+    }
+}
+fn line_range(line: usize) -> Value {
+    let line = line.saturating_sub(1);
+    json!({
+        "start": { "line": line, "character": 0 },
+        "end": { "line": line + 1, "character": 0 },
+    })
+}
+
+#[test]
+fn definition_and_references() {
+    let analysis = Analysis::new(
+        r#"var total = 0;
+fun add(n) {
+  var sum = total + n;
+  return sum;
+}
+print add(1) + total;
+"#,
+    );
+    // `total` 在第 3 行的引用跳到第 1 行的声明
+    let definition = analysis.definition("file:///a.lox", (3, 13));
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 0, "character": 4 })
+    );
+    let references = analysis.references("file:///a.lox", (1, 5), true);
+    assert_eq!(references.as_array().unwrap().len(), 3);
+    // 参数 n 只在函数里可见
+    let labels = |value: Value| -> Vec<String> {
+        let items = value.as_array().unwrap().clone();
+        items
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert!(labels(analysis.completion((4, 2))).contains(&"n".to_string()));
+    assert!(!labels(analysis.completion((6, 0))).contains(&"n".to_string()));
+    assert!(analysis.diagnostics().as_array().unwrap().is_empty());
+}
+
+#[test]
+fn columns_are_utf16() {
+    // "é" 是两个字节、一个 UTF-16 单元，"😀" 是四个字节、两个 UTF-16 单元
+    let analysis = Analysis::new("var s = \"é😀\"; var total = s;\nprint total;\n");
+    let definition = analysis.definition("file:///a.lox", (2, 6));
+    assert_eq!(
+        definition["range"],
+        json!({
+            "start": { "line": 0, "character": 19 },
+            "end": { "line": 0, "character": 24 },
+        })
+    );
+    // 客户端按 UTF-16 发来的位置也要换算回字节
+    let references = analysis.references("file:///a.lox", (1, 27), true);
+    let starts: Vec<_> = references
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| location["range"]["start"].clone())
+        .collect();
+    assert_eq!(
+        starts,
+        [
+            json!({ "line": 0, "character": 4 }),
+            json!({ "line": 0, "character": 27 }),
+        ]
+    );
+}
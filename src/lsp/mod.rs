@@ -0,0 +1,222 @@
+mod analysis;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{json, Value};
+
+pub use analysis::Analysis;
+
+use crate::resolver::Position;
+
+// 通过 stdin/stdout 跑 LSP，文档都是整篇同步
+pub struct Server {
+    documents: HashMap<String, Analysis>,
+    shutdown: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self {
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    // 收到 `exit` 或输入结束时返回；先收到 `shutdown` 才算正常退出
+    pub fn run(mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+        while let Some(message) = read_message(&mut input)? {
+            if message["method"] == "exit" {
+                break;
+            }
+            for reply in self.handle(&message) {
+                write_message(&mut output, &reply)?;
+            }
+        }
+        Ok(self.shutdown)
+    }
+
+    // 返回要发回去的消息：请求的响应，或者诊断通知
+    fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "lox" },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                return vec![self.update(uri, text)];
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                let Some(text) = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return vec![];
+                };
+                return vec![self.update(uri, text)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish_diagnostics(&uri, json!([]))];
+            }
+            _ if id.is_none() => return vec![],
+            _ => match (self.documents.get(&uri), position(params)) {
+                (Some(analysis), Some(position)) => match method {
+                    "textDocument/definition" => analysis.definition(&uri, position),
+                    "textDocument/references" => {
+                        let include = params["context"]["includeDeclaration"]
+                            .as_bool()
+                            .unwrap_or(true);
+                        analysis.references(&uri, position, include)
+                    }
+                    "textDocument/hover" => analysis.hover(position),
+                    "textDocument/completion" => analysis.completion(position),
+                    _ => return vec![method_not_found(id, method)],
+                },
+                (Some(analysis), None) if method == "textDocument/documentSymbol" => {
+                    analysis.document_symbols()
+                }
+                (None, _) if method.starts_with("textDocument/") => Value::Null,
+                _ => return vec![method_not_found(id, method)],
+            },
+        };
+        match id {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![],
+        }
+    }
+
+    fn update(&mut self, uri: String, text: &str) -> Value {
+        let analysis = Analysis::new(text);
+        let diagnostics = publish_diagnostics(&uri, analysis.diagnostics());
+        self.documents.insert(uri, analysis);
+        diagnostics
+    }
+}
+
+// LSP 的位置行列都从 0 开始，Resolver 的行从 1 开始
+fn position(params: &Value) -> Option<Position> {
+    let line = params["position"]["line"].as_u64()?;
+    let character = params["position"]["character"].as_u64()?;
+    Some((line as usize + 1, character as usize))
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn method_not_found(id: Option<Value>, method: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+    })
+}
+
+// `Content-Length` 头，空行，然后是 JSON；输入结束返回 None
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[test]
+fn publishes_diagnostics_and_answers_requests() {
+    let frame = |message: Value| {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    };
+    let uri = "file:///a.lox";
+    let input = [
+        frame(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })),
+        frame(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "text": "var a = 1;\nprint a +;\n" } },
+        })),
+        frame(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "textDocument/hover",
+            "params": { "textDocument": { "uri": uri }, "position": { "line": 0, "character": 4 } },
+        })),
+        frame(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" })),
+        frame(json!({ "jsonrpc": "2.0", "method": "exit" })),
+    ]
+    .concat();
+    let mut output = vec![];
+    let clean = Server::new().run(input.as_bytes(), &mut output).unwrap();
+    assert!(clean);
+
+    let mut output = &output[..];
+    let mut replies = vec![];
+    while let Some(message) = read_message(&mut output).unwrap() {
+        replies.push(message);
+    }
+    assert_eq!(replies.len(), 4);
+    let diagnostics = &replies[1]["params"]["diagnostics"];
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(
+        replies[2]["result"]["contents"]["value"],
+        "(global variable) a, declared on line 1"
+    );
+}
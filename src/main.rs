@@ -1,4 +1,4 @@
-use std::process;
+use std::{process, thread};
 
 use clap::Parser;
 use codecrafters_interpreter::ast_printer::AstPrinter;
@@ -8,8 +8,37 @@ use codecrafters_interpreter::error::HAD_ERROR;
 use codecrafters_interpreter::error::HAD_RUNTIME_ERROR;
 use codecrafters_interpreter::lox::Lox;
 
+// 解释器每层 Lox 调用在 Rust 栈上要用掉的大概字节数（debug 构建约 30KB），留足余量
+const STACK_PER_CALL: usize = 128 * 1024;
+const STACK_BASE: usize = 16 * 1024 * 1024;
+
 fn main() {
     let x = ArgsParser::parse();
+    // 主线程的栈只有 8MB，到不了 --max-depth 就会溢出，换一个按上限分配栈的线程来跑
+    let stack_size = x
+        .max_depth
+        .saturating_mul(STACK_PER_CALL)
+        .saturating_add(STACK_BASE);
+    let max_depth = x.max_depth;
+    let interpreter = thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || run(x));
+    let interpreter = match interpreter {
+        Ok(interpreter) => interpreter,
+        Err(e) => {
+            eprintln!(
+                "Can't start the interpreter with --max-depth {}: {}",
+                max_depth, e
+            );
+            process::exit(2)
+        }
+    };
+    if interpreter.join().is_err() {
+        process::exit(101)
+    }
+}
+
+fn run(x: ArgsParser) {
     let resolver = x.resolver.unwrap_or(true);
     match x.cmds {
         Cmd::Tokenize { file } => {
@@ -28,7 +57,36 @@ fn main() {
             Lox::evaluate(file);
         }
         Cmd::Run { file } => {
-            let res = Lox::run_file(file, resolver);
+            let res = Lox::run_file(file, resolver, x.optimize, x.max_depth);
+        }
+        Cmd::Ast { file, format } => {
+            Lox::ast(file, format);
+        }
+        Cmd::RunAst { file } => {
+            let _ = Lox::run_ast_file(file, resolver, x.optimize, x.max_depth);
+        }
+        Cmd::Check { file } => {
+            Lox::check(file);
+        }
+        Cmd::Lint {
+            file,
+            config,
+            enable,
+            disable,
+            format,
+        } => {
+            if !Lox::lint(file, config, enable, disable, format) {
+                process::exit(1)
+            }
+        }
+        Cmd::Fmt { files, check } => {
+            if !Lox::fmt(files, check) {
+                process::exit(1)
+            }
+        }
+        Cmd::Lsp => {
+            // 协议占用 stdout，不走后面的退出码逻辑
+            process::exit(if Lox::lsp() { 0 } else { 1 })
         }
     }
     unsafe {
@@ -0,0 +1,303 @@
+use std::rc::Rc;
+
+use crate::{
+    data_types::scaler::Scalar,
+    expr::{literal::LiteralExpr, Expr},
+    stmt::{block::BlockStmt, function::FunctionStmt, Stmt},
+    token_type::TokenType,
+};
+
+// 可选的优化：折叠字面量运算，去掉 `if (false)`、`while (false)`，条件里的 `!!x` 化简成 `x`
+// 会在运行时报错的运算不折叠；只移动节点不重新分配，Resolver 的记录仍然有效
+pub struct Optimizer;
+
+impl Optimizer {
+    pub fn optimize(stmts: &mut Vec<Stmt>) {
+        optimize_stmts(stmts);
+    }
+
+    // for 脱糖成 while 会复制节点，要在 resolve 之前做，resolver 记录的才是脱糖后的地址
+    pub fn lower(stmts: &mut [Stmt]) {
+        for stmt in stmts {
+            lower_stmt(stmt);
+        }
+    }
+}
+
+fn lower_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::For(for_stmt) => {
+            let lowered = for_stmt.lower();
+            *stmt = lowered;
+            lower_stmt(stmt);
+        }
+        Stmt::Block(block) => Optimizer::lower(&mut block.statements),
+        Stmt::If(if_stmt) => {
+            lower_stmt(&mut if_stmt.then_branch);
+            if let Some(else_branch) = &mut if_stmt.else_branch {
+                lower_stmt(else_branch);
+            }
+        }
+        Stmt::While(while_stmt) => lower_stmt(&mut while_stmt.body),
+        Stmt::DoWhile(do_while) => lower_stmt(&mut do_while.body),
+        Stmt::Function(function) => lower_function(function),
+        Stmt::Class(class) => {
+            for method in class.methods.iter_mut().chain(&mut class.class_methods) {
+                lower_function(method);
+            }
+        }
+        Stmt::Trait(trait_stmt) => {
+            for method in trait_stmt
+                .methods
+                .iter_mut()
+                .chain(&mut trait_stmt.class_methods)
+            {
+                lower_function(method);
+            }
+        }
+        Stmt::Var(_) | Stmt::Expression(_) | Stmt::Print(_) | Stmt::Return(_) => {}
+    }
+}
+
+fn lower_function(function: &mut Rc<FunctionStmt>) {
+    if let Some(function) = Rc::get_mut(function) {
+        Optimizer::lower(&mut function.fn_body);
+    }
+}
+
+fn optimize_stmts(stmts: &mut Vec<Stmt>) {
+    for stmt in stmts.iter_mut() {
+        optimize_stmt(stmt);
+    }
+    // 分支里不能直接声明变量，删掉语句不会打乱作用域里的 slot
+    stmts.retain(|stmt| !is_empty_stmt(stmt));
+}
+
+fn optimize_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Var(var) => {
+            if let Some(initializer) = &mut var.initializer {
+                optimize_expr(initializer);
+            }
+        }
+        Stmt::Expression(expr) => optimize_expr(&mut expr.expression),
+        Stmt::Print(print) => optimize_expr(&mut print.expression),
+        Stmt::Block(block) => optimize_stmts(&mut block.statements),
+        Stmt::If(if_stmt) => {
+            optimize_condition(&mut if_stmt.condition);
+            optimize_stmt(&mut if_stmt.then_branch);
+            if let Some(else_branch) = &mut if_stmt.else_branch {
+                optimize_stmt(else_branch);
+            }
+            let Some(condition) = truthiness(&if_stmt.condition) else {
+                return;
+            };
+            let branch = if condition {
+                Some(std::mem::replace(&mut if_stmt.then_branch, empty_stmt()))
+            } else {
+                if_stmt.else_branch.take()
+            };
+            *stmt = branch.unwrap_or_else(empty_stmt);
+        }
+        Stmt::While(while_stmt) => {
+            optimize_condition(&mut while_stmt.condition);
+            if truthiness(&while_stmt.condition) == Some(false) {
+                *stmt = empty_stmt();
+                return;
+            }
+            optimize_stmt(&mut while_stmt.body);
+        }
+        Stmt::For(for_stmt) => {
+            if let Some(initializer) = &mut for_stmt.initializer {
+                optimize_stmt(initializer);
+            }
+            if let Some(condition) = &mut for_stmt.condition {
+                optimize_condition(condition);
+            }
+            if let Some(increment) = &mut for_stmt.increment {
+                optimize_expr(increment);
+            }
+            optimize_stmt(&mut for_stmt.body);
+        }
+        Stmt::DoWhile(do_while) => {
+            optimize_stmt(&mut do_while.body);
+            optimize_condition(&mut do_while.condition);
+        }
+        Stmt::Function(function) => optimize_function(function),
+        Stmt::Return(ret) => {
+            if let Some(value) = &mut ret.value {
+                optimize_expr(value);
+            }
+        }
+        Stmt::Class(class) => {
+            for method in class.methods.iter_mut().chain(&mut class.class_methods) {
+                optimize_function(method);
+            }
+        }
+        Stmt::Trait(trait_stmt) => {
+            for method in trait_stmt
+                .methods
+                .iter_mut()
+                .chain(&mut trait_stmt.class_methods)
+            {
+                optimize_function(method);
+            }
+        }
+    }
+}
+
+// 执行前函数声明还没有被别的地方引用
+fn optimize_function(function: &mut Rc<FunctionStmt>) {
+    let Some(function) = Rc::get_mut(function) else {
+        return;
+    };
+    for param in &mut function.params {
+        if let Some(default) = &mut param.default {
+            optimize_expr(default);
+        }
+    }
+    optimize_stmts(&mut function.fn_body);
+}
+
+// 被删掉的语句先换成没有语句、没有位置的空块，最后统一从列表里去掉
+fn empty_stmt() -> Stmt {
+    BlockStmt::from(vec![]).into()
+}
+fn is_empty_stmt(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Block(block) if block.statements.is_empty() && block.line == 0)
+}
+
+// 条件只看真假，`!!x` 和 `x` 一样
+fn optimize_condition(condition: &mut Expr) {
+    optimize_expr(condition);
+    while let Expr::Unary(outer) = condition {
+        let Expr::Unary(inner) = &mut outer.right else {
+            break;
+        };
+        if outer.operator.t_type != TokenType::BANG || inner.operator.t_type != TokenType::BANG {
+            break;
+        }
+        let x = std::mem::replace(&mut inner.right, LiteralExpr::nil().into());
+        *condition = x;
+    }
+}
+
+fn optimize_expr(expr: &mut Expr) {
+    match expr {
+        Expr::Binary(binary) => {
+            optimize_expr(&mut binary.left);
+            optimize_expr(&mut binary.right);
+            let (Some(left), Some(right)) = (literal(&binary.left), literal(&binary.right)) else {
+                return;
+            };
+            if let Some(value) = fold_binary(left, right, &binary.operator.t_type) {
+                *expr = LiteralExpr { value }.into();
+            }
+        }
+        Expr::Unary(unary) => {
+            optimize_expr(&mut unary.right);
+            let Some(right) = literal(&unary.right) else {
+                return;
+            };
+            let value = match (&unary.operator.t_type, right) {
+                (TokenType::BANG, right) => !right.clone(),
+                (TokenType::MINUS, Scalar::Number(n)) => Scalar::Number(-n),
+                _ => return,
+            };
+            *expr = LiteralExpr { value }.into();
+        }
+        Expr::Grouping(grouping) => {
+            optimize_expr(&mut grouping.expression);
+            if let Some(value) = literal(&grouping.expression) {
+                let value = value.clone();
+                *expr = LiteralExpr { value }.into();
+            }
+        }
+        Expr::Logical(logical) => {
+            optimize_expr(&mut logical.left);
+            optimize_expr(&mut logical.right);
+        }
+        Expr::Assign(assign) => optimize_expr(&mut assign.value),
+        Expr::Call(call) => {
+            optimize_expr(&mut call.callee);
+            for arg in &mut call.arguments {
+                optimize_expr(arg);
+            }
+            for arg in &mut call.named {
+                optimize_expr(&mut arg.value);
+            }
+        }
+        Expr::Get(get) => optimize_expr(&mut get.object),
+        Expr::Set(set) => {
+            optimize_expr(&mut set.object);
+            optimize_expr(&mut set.value);
+        }
+        Expr::Index(index) => {
+            optimize_expr(&mut index.object);
+            optimize_expr(&mut index.index);
+        }
+        Expr::Spread(spread) => optimize_expr(&mut spread.expression),
+        Expr::Variable(_) | Expr::Literal(_) | Expr::This(_) | Expr::Super(_) => {}
+    }
+}
+
+fn literal(expr: &Expr) -> Option<&Scalar> {
+    match expr {
+        Expr::Literal(literal) => Some(&literal.value),
+        _ => None,
+    }
+}
+
+fn truthiness(condition: &Expr) -> Option<bool> {
+    literal(condition).map(|value| !matches!(value, Scalar::Nil | Scalar::Bool(false)))
+}
+
+// 和 Evaluator 里的二元运算一致；类型不对会在运行时报错的组合返回 None
+fn fold_binary(left: &Scalar, right: &Scalar, operator: &TokenType) -> Option<Scalar> {
+    use TokenType::*;
+    let (left, right) = (left.clone(), right.clone());
+    match (operator, &left, &right) {
+        (EqualEqual, ..) => Some(Scalar::Bool(left == right)),
+        (BangEqual, ..) => Some(Scalar::Bool(left != right)),
+        (PLUS, Scalar::String(_), Scalar::String(_)) => Some(left + right),
+        (_, Scalar::Number(_), Scalar::Number(_)) => match operator {
+            PLUS => Some(left + right),
+            MINUS => Some(left - right),
+            STAR => Some(left * right),
+            SLASH => Some(left / right),
+            GREATER => Some(Scalar::Bool(left > right)),
+            GreaterEqual => Some(Scalar::Bool(left >= right)),
+            LESS => Some(Scalar::Bool(left < right)),
+            LessEqual => Some(Scalar::Bool(left <= right)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[test]
+fn folds_constants_and_keeps_errors() {
+    use crate::{ast_printer::AstPrinter, test_util::parse_source};
+
+    let mut stmts = parse_source(
+        r#"
+print 1 + 2 * 3;
+print "a" + "b";
+print "a" - 1;
+if (false) print 1; else print 2;
+while (false) print 3;
+if (!!x) print 4;
+"#,
+    );
+    Optimizer::optimize(&mut stmts);
+    let printed: Vec<_> = stmts.iter().map(|stmt| stmt.print(true)).collect();
+    // 会在运行时报错的 `"a" - 1` 不折叠
+    let expected = [
+        "(print 7.0)",
+        "(print ab)",
+        "(print (- a 1.0))",
+        "(print 2.0)",
+        "(if x (print 4.0))",
+    ];
+    assert_eq!(printed, expected);
+}
@@ -5,14 +5,16 @@ use std::rc::Rc;
 use crate::{
     error::{my_error_token, MyResult},
     expr::{
-        assign::AssignExpr, binary::BinaryExpr, call::CallExpr, get::GetExpr,
-        grouping::GroupingExpr, literal::LiteralExpr, logical::LogicalExpr, set::SetExpr,
-        super_expr::SuperExpr, this::ThisExpr, unary::UnaryExpr, variable::VariableExpr, Expr,
+        assign::AssignExpr, binary::BinaryExpr, call::{CallExpr, NamedArg}, get::GetExpr,
+        grouping::GroupingExpr, index::IndexExpr, literal::LiteralExpr, logical::LogicalExpr,
+        set::SetExpr, spread::SpreadExpr, super_expr::SuperExpr, this::ThisExpr,
+        unary::UnaryExpr, variable::VariableExpr, Expr,
     },
     stmt::{
-        block::BlockStmt, class_stmt::ClassStmt, expression::ExpressionStmt,
-        function::FunctionStmt, if_stmt::IfStmt, print::PrintStmt, return_stmt::ReturnStmt,
-        var::VarStmt, while_stmt::WhileStmt, Stmt,
+        block::BlockStmt, class_stmt::{ClassStmt, FieldDecl}, do_while_stmt::DoWhileStmt,
+        expression::ExpressionStmt, for_stmt::ForStmt,
+        function::{FunctionStmt, Param}, if_stmt::IfStmt, print::PrintStmt, return_stmt::ReturnStmt,
+        trait_stmt::TraitStmt, var::VarStmt, while_stmt::WhileStmt, Stmt,
     },
     token::Token,
     token_type::{CmpTokenType, TokenType},
@@ -21,6 +23,8 @@ use crate::{
 use parse_error::ParseError;
 use TokenType::*;
 
+type Methods = Vec<Rc<FunctionStmt>>;
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
@@ -65,7 +69,7 @@ impl Parser {
 
         while !self.is_at_end() {
             let next = self.peek_unchecked();
-            if [WHILE, CLASS, FUN, FOR, IF, PRINT, RETURN, VAR].contains(&next.t_type) {
+            if [WHILE, DO, CLASS, TRAIT, FUN, FOR, IF, PRINT, RETURN, VAR].contains(&next.t_type) {
                 return;
             }
             if [SEMICOLON].contains(&next.t_type) {
@@ -95,8 +99,11 @@ impl Parser {
         if self.match_advance_unchecked([CLASS]).is_some() {
             return self.class_declaration();
         };
+        if self.match_advance_unchecked([TRAIT]).is_some() {
+            return self.trait_declaration();
+        };
         if self.match_advance_unchecked([FUN]).is_some() {
-            return Ok(self.function_declaration()?.into());
+            return Ok(self.function_declaration(false)?.into());
         };
         if self.match_advance_unchecked([VAR]).is_some() {
             return self.var_declaration();
@@ -111,34 +118,124 @@ impl Parser {
         if self.match_advance_unchecked([LESS]).is_some() {
             superclass = Some(self.consume(IDENTIFIER(String::new()), "Expect superclass name.")?);
         }
-        self.consume(LeftBrace, "Expect '{' after class name.")?;
-        let mut methods = vec![];
-        while !self.check_unchecked([&RightBrace]) {
-            methods.push(Rc::new(self.function_declaration()?));
+        let mut traits = vec![];
+        if self.match_advance_unchecked([WITH]).is_some() {
+            loop {
+                let name = self.consume(IDENTIFIER(String::new()), "Expect trait name.")?;
+                traits.push(VariableExpr { name }.into());
+                if self.match_advance_unchecked([COMMA]).is_none() {
+                    break;
+                }
+            }
         }
-        self.consume(RightBrace, "Expect '}' after class body.")?;
+        self.consume(LeftBrace, "Expect '{' after class name.")?;
+        let mut fields = vec![];
+        while self.match_advance_unchecked([VAR]).is_some() {
+            let name = self.consume(IDENTIFIER(String::new()), "Expect field name.")?;
+            let type_ann = self.type_annotation()?;
+            self.consume(SEMICOLON, "Expect ';' after field declaration.")?;
+            fields.push(FieldDecl { name, type_ann });
+        }
+        let (methods, class_methods) = self.class_body("class")?;
         Ok(ClassStmt {
             name,
             methods,
+            class_methods,
             superclass: superclass.map(|name| VariableExpr { name }.into()),
+            traits,
+            fields,
         }
         .into())
     }
 
-    fn function_declaration(&mut self) -> MyResult<FunctionStmt> {
+    fn trait_declaration(&mut self) -> MyResult<Stmt> {
+        let name = self.consume(IDENTIFIER(String::new()), "Expect trait name.")?;
+        self.consume(LeftBrace, "Expect '{' after trait name.")?;
+        let (methods, class_methods) = self.class_body("trait")?;
+        Ok(TraitStmt {
+            name,
+            methods,
+            class_methods,
+        }
+        .into())
+    }
+
+    // 类和 trait 共用的方法列表，`class` 开头的是静态方法
+    fn class_body(&mut self, kind: &str) -> MyResult<(Methods, Methods)> {
+        let mut methods = vec![];
+        let mut class_methods = vec![];
+        while !self.check_unchecked([&RightBrace, &EOF]) {
+            if self.match_advance_unchecked([CLASS]).is_some() {
+                class_methods.push(Rc::new(self.function_declaration(false)?));
+            } else {
+                methods.push(Rc::new(self.function_declaration(true)?));
+            }
+        }
+        self.consume(RightBrace, format!("Expect '}}' after {} body.", kind))?;
+        Ok((methods, class_methods))
+    }
+
+    // `: Type`，没有冒号时返回 None
+    fn type_annotation(&mut self) -> MyResult<Option<Token>> {
+        if self.match_advance_unchecked([COLON]).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(self.consume(IDENTIFIER(String::new()), "Expect type name.")?))
+    }
+
+    // allow_getter: 类里没有参数列表的方法是 getter，`area { ... }`、`area: Number { ... }`
+    fn function_declaration(&mut self, allow_getter: bool) -> MyResult<FunctionStmt> {
         let name = self.consume(IDENTIFIER(String::new()), "")?;
-        let _ = self.consume(LeftParen, "")?;
+        if allow_getter && self.check_unchecked([&LeftBrace, &COLON]) {
+            let return_type = self.type_annotation()?;
+            self.consume(LeftBrace, "Expect '{' before getter body.")?;
+            let fn_body = self.block_stmt()?;
+            return Ok(FunctionStmt {
+                name,
+                params: vec![],
+                rest: None,
+                fn_body,
+                getter: true,
+                return_type,
+            });
+        }
+        let _ = self.consume(LeftParen, "Expect '(' after function name.")?;
         let mut params = vec![];
+        let mut rest = None;
 
         if !self.check_unchecked([&RightParen]) {
-            params.push(self.consume(IDENTIFIER(String::new()), "Expect parameter name.")?);
-
-            while self.match_advance_unchecked([COMMA]).is_some() {
-                params.push(self.consume(IDENTIFIER(String::new()), "Expect parameter name.")?);
+            loop {
+                if self.match_advance_unchecked([DotDotDot]).is_some() {
+                    let name = self.consume(IDENTIFIER(String::new()), "Expect parameter name.")?;
+                    rest = Some(name);
+                    if !self.check_unchecked([&RightParen]) {
+                        let message = "Rest parameter must be last.".to_string();
+                        return MyErr!(,ParseError::NotExpected(self.peek_unchecked(), message));
+                    }
+                    break;
+                }
+                let name = self.consume(IDENTIFIER(String::new()), "Expect parameter name.")?;
+                let type_ann = self.type_annotation()?;
+                let mut default = None;
+                if self.match_advance_unchecked([EQUAL]).is_some() {
+                    default = Some(self.expression()?);
+                } else if params.iter().any(|p: &Param| p.default.is_some()) {
+                    let message = "Parameter without a default can't follow one with a default.";
+                    return MyErr!(,ParseError::NotExpected(name, message.to_string()));
+                }
+                params.push(Param {
+                    name,
+                    default,
+                    type_ann,
+                });
+                if self.match_advance_unchecked([COMMA]).is_none() {
+                    break;
+                }
             }
         }
 
         let _ = self.consume(RightParen, "Expect ')' after parameters.")?;
+        let return_type = self.type_annotation()?;
         let _ = self.consume(LeftBrace, r"Expect '{' after parameters.")?;
 
         let fn_body = self.block_stmt()?;
@@ -146,11 +243,15 @@ impl Parser {
         Ok(FunctionStmt {
             name,
             params,
+            rest,
             fn_body,
+            getter: false,
+            return_type,
         })
     }
     fn var_declaration(&mut self) -> MyResult<Stmt> {
         let name = self.consume(IDENTIFIER(String::new()), "")?;
+        let type_ann = self.type_annotation()?;
         let mut initializer = None;
         if self.match_advance_unchecked([EQUAL]).is_some() {
             let next = self.expression()?;
@@ -159,7 +260,12 @@ impl Parser {
         }
         self.consume(SEMICOLON, "Var Stmt Expect '}' after block.")?;
 
-        Ok(VarStmt { name, initializer }.into())
+        Ok(VarStmt {
+            name,
+            initializer,
+            type_ann,
+        }
+        .into())
     }
     fn statement(&mut self) -> MyResult<Stmt> {
         if self.match_advance_unchecked([RETURN]).is_some() {
@@ -177,10 +283,17 @@ impl Parser {
         if self.match_advance_unchecked([WHILE]).is_some() {
             return self.while_stmt();
         }
-        if self.match_advance_unchecked([LeftBrace]).is_some() {
-            return self
-                .block_stmt()
-                .map(|v| BlockStmt { statements: v }.into());
+        if self.match_advance_unchecked([DO]).is_some() {
+            return self.do_while_stmt();
+        }
+        if let Some(brace) = self.match_advance_unchecked([LeftBrace]) {
+            return self.block_stmt().map(|statements| {
+                BlockStmt {
+                    statements,
+                    line: brace.line,
+                }
+                .into()
+            });
         }
 
         self.expression_stmt()
@@ -211,6 +324,7 @@ impl Parser {
         }
     }
     fn while_stmt(&mut self) -> MyResult<Stmt> {
+        let line = self.previous_unchecked().line;
         self.consume(LeftParen, "Expect '(' after 'while'.")?;
 
         let condition = self.expression()?;
@@ -218,7 +332,32 @@ impl Parser {
 
         let body = self.statement()?;
 
-        Ok(WhileStmt { condition, body }.into())
+        Ok(WhileStmt {
+            condition,
+            body,
+            line,
+        }
+        .into())
+    }
+    fn do_while_stmt(&mut self) -> MyResult<Stmt> {
+        let keyword = self.previous_unchecked();
+        let body = self.statement()?;
+
+        self.consume(WHILE, "Expect 'while' after do body.")?;
+        self.consume(LeftParen, "Expect '(' after 'while'.")?;
+        if self.check_unchecked([&RightParen]) {
+            return MyErr!(,ParseError::NotExpected(self.peek_unchecked(), "Expect condition after 'while'.".to_string()));
+        }
+        let condition = self.expression()?;
+        self.consume(RightParen, "Expect ')' after do-while condition.")?;
+        self.consume(SEMICOLON, "Expect ';' after do-while condition.")?;
+
+        Ok(DoWhileStmt {
+            keyword,
+            body,
+            condition,
+        }
+        .into())
     }
     fn return_stmt(&mut self) -> MyResult<Stmt> {
         let keyword = self.previous_unchecked();
@@ -230,57 +369,40 @@ impl Parser {
         Ok(ReturnStmt { keyword, value }.into())
     }
     fn for_stmt(&mut self) -> MyResult<Stmt> {
-        self.consume(LeftParen, "Expect '(' after 'loop'.")?;
-        let mut body = BlockStmt::from([]);
-        let mut initial = None;
+        let keyword = self.previous_unchecked();
+        self.consume(LeftParen, "Expect '(' after 'for'.")?;
 
+        let mut initializer = None;
         if self.match_advance_unchecked([SEMICOLON]).is_none() {
             if self.match_advance_unchecked([VAR]).is_some() {
-                initial = Some(self.var_declaration()?)
+                initializer = Some(self.var_declaration()?)
             } else {
-                initial = Some(self.expression_stmt()?);
+                initializer = Some(self.expression_stmt()?);
             }
         }
 
-        let second = self.peek_unchecked().t_type;
-
-        let condition;
-        if second != SEMICOLON {
-            condition = self.expression()?;
-        } else {
-            condition = LiteralExpr::from(true).into();
+        let mut condition = None;
+        if !self.check_unchecked([&SEMICOLON]) {
+            condition = Some(self.expression()?);
         }
         self.consume(SEMICOLON, "Expect ';' after loop condition.")?;
 
-        let third = self.peek_unchecked().t_type;
-
         let mut increment = None;
-        if third != RightParen {
+        if !self.check_unchecked([&RightParen]) {
             increment = Some(self.expression()?);
         }
+        self.consume(RightParen, "Expect ')' after for clauses.")?;
 
-        self.consume(RightParen, "Expect ')' after loop condition.")?;
-
-        body.push(self.statement()?);
-
-        if let Some(incremnt) = increment {
-            body.push(ExpressionStmt::from(incremnt).into());
-        }
+        let body = self.statement()?;
 
-        let mut while_or_block: Stmt = WhileStmt {
+        Ok(ForStmt {
+            keyword,
+            initializer,
             condition,
-            body: body.into(),
+            increment,
+            body,
         }
-        .into();
-        // println!("init {:#?}", initial);
-
-        if let Some(init) = initial {
-            let bb = [init, while_or_block];
-            // println!("for {:#?}", bb);
-
-            while_or_block = BlockStmt::from(bb).into();
-        }
-        Ok(while_or_block)
+        .into())
     }
     // 为了给函数使用，返回一个Vec<Stmt>，而不是Stmt
     fn block_stmt(&mut self) -> MyResult<Vec<Stmt>> {
@@ -310,7 +432,7 @@ impl Parser {
         self.assignment()
     }
     fn assignment(&mut self) -> MyResult<Expr> {
-        let expr = self.or()?;
+        let expr = self.coalesce()?;
         if let Some(equal) = self.match_advance_unchecked([EQUAL]) {
             let value = self.assignment()?;
             match expr {
@@ -326,6 +448,7 @@ impl Parser {
                         object: get_expr.object,
                         name: get_expr.name,
                         value,
+                        optional: get_expr.optional,
                     }
                     .into())
                 }
@@ -337,6 +460,18 @@ impl Parser {
 
         Ok(expr)
     }
+    fn coalesce(&mut self) -> MyResult<Expr> {
+        let mut expr = self.or()?;
+        while let Some(x) = self.match_advance_unchecked([QuestionQuestion]) {
+            expr = LogicalExpr {
+                left: expr,
+                right: self.or()?,
+                operator: x,
+            }
+            .into();
+        }
+        Ok(expr)
+    }
     fn or(&mut self) -> MyResult<Expr> {
         let mut expr = self.and()?;
         while let Some(x) = self.match_advance_unchecked([OR]) {
@@ -381,7 +516,7 @@ impl Parser {
         let mut expr = self.term()?;
 
         while let Some(operator) =
-            self.match_advance_unchecked([GREATER, GreaterEqual, LESS, LessEqual])
+            self.match_advance_unchecked([GREATER, GreaterEqual, LESS, LessEqual, IS])
         {
             expr = BinaryExpr {
                 left: expr,
@@ -443,32 +578,69 @@ impl Parser {
             let next = self.peek_unchecked();
             if next.t_type == LeftParen {
                 self.current += 1;
-                expr = self.finish_call(expr)?;
+                expr = self.finish_call(expr, false)?;
             } else if next.t_type == DOT {
                 self.current += 1;
                 expr = GetExpr {
                     object: expr,
                     name: self
                         .consume(IDENTIFIER(String::new()), "Expect property name after '.'.")?,
+                    optional: false,
+                }
+                .into();
+            } else if next.t_type == LeftBracket {
+                self.current += 1;
+                let index = self.expression()?;
+                let bracket = self.consume(RightBracket, "Expect ']' after index.")?;
+                expr = IndexExpr {
+                    object: expr,
+                    bracket,
+                    index,
                 }
                 .into();
+            } else if next.t_type == QuestionDot {
+                self.current += 1;
+                if self.match_advance_unchecked([LeftParen]).is_some() {
+                    expr = self.finish_call(expr, true)?;
+                } else {
+                    expr = GetExpr {
+                        object: expr,
+                        name: self.consume(
+                            IDENTIFIER(String::new()),
+                            "Expect property name after '?.'.",
+                        )?,
+                        optional: true,
+                    }
+                    .into();
+                }
             } else {
                 break;
             }
         }
         Ok(expr)
     }
-    fn finish_call(&mut self, callee: Expr) -> MyResult<Expr> {
+    fn finish_call(&mut self, callee: Expr, optional: bool) -> MyResult<Expr> {
         let mut arguments = vec![];
+        let mut named = vec![];
         let is_right_paren = self.check_unchecked([&RightParen]);
 
         if !is_right_paren {
-            arguments.push(self.expression()?);
-            while self.match_advance_unchecked([COMMA]).is_some() {
-                if arguments.len() >= 255 {
+            loop {
+                if arguments.len() + named.len() >= 255 {
                     return MyErr!(,ParseError::NotExpected(self.peek_unchecked(), "Can't have more than 255 arguments.".to_string()));
                 }
-                arguments.push(self.expression()?);
+                if let Some(name) = self.named_argument() {
+                    let value = self.expression()?;
+                    named.push(NamedArg { name, value });
+                } else if !named.is_empty() {
+                    let message = "Positional argument can't follow named arguments.";
+                    return MyErr!(,ParseError::NotExpected(self.peek_unchecked(), message.to_string()));
+                } else {
+                    arguments.push(self.argument()?);
+                }
+                if self.match_advance_unchecked([COMMA]).is_none() {
+                    break;
+                }
             }
         }
 
@@ -476,10 +648,35 @@ impl Parser {
         Ok(CallExpr {
             callee,
             arguments,
+            named,
             parent,
+            optional,
         }
         .into())
     }
+    // `name:` 开头的是命名参数
+    fn named_argument(&mut self) -> Option<Token> {
+        let next = self.peek_unchecked();
+        let is_colon = self
+            .tokens
+            .get(self.current + 1)
+            .is_some_and(|t| t.t_type == COLON);
+        if matches!(next.t_type, IDENTIFIER(_)) && is_colon {
+            self.current += 2;
+            return Some(next);
+        }
+        None
+    }
+    fn argument(&mut self) -> MyResult<Expr> {
+        if let Some(ellipsis) = self.match_advance_unchecked([DotDotDot]) {
+            return Ok(SpreadExpr {
+                ellipsis,
+                expression: self.expression()?,
+            }
+            .into());
+        }
+        self.expression()
+    }
     fn primary(&mut self) -> MyResult<Expr> {
         let next = self.advance_unchecked();
         let expr = match next.t_type {
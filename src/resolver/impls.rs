@@ -1,6 +1,6 @@
 use crate::{error::my_error_token, expr::Expr, stmt::Stmt};
 
-use super::{ClassType, FunctionType, Resolver, ResolverWalk};
+use super::{Binding, BindingKind, ClassType, FunctionType, Resolver, ResolverWalk};
 
 impl ResolverWalk<Expr> for Resolver<'_> {
     fn resolve(&mut self, expr: &Expr) {
@@ -9,17 +9,26 @@ impl ResolverWalk<Expr> for Resolver<'_> {
                 let name = variable_expr.name.lexeme.clone();
                 let cur = self.cur_scope();
                 // println!("cur: {:?}, name: {}", cur, name);
-                if cur.map_or(false, |scope| matches!(scope.get(&name), Some(false))) {
+                if cur
+                    .and_then(|scope| scope.get(&name))
+                    .is_some_and(|binding| !binding.defined)
+                {
                     my_error_token(
                         variable_expr.name.clone(),
                         "Can't read local variable in its own initializer.".to_string(),
                     );
                 }
-                self.resolve_local(expr, &name);
+                self.record_reference(&variable_expr.name);
+                if !self.resolve_local(expr, &name, true) {
+                    self.check_global(&variable_expr.name, true);
+                }
             }
             Expr::Assign(assign_expr) => {
                 self.resolve(&assign_expr.value);
-                self.resolve_local(expr, &assign_expr.name.lexeme);
+                self.record_reference(&assign_expr.name);
+                if !self.resolve_local(expr, &assign_expr.name.lexeme, false) {
+                    self.check_global(&assign_expr.name, false);
+                }
             }
             Expr::Binary(binary_expr) => {
                 self.resolve(&binary_expr.left);
@@ -38,6 +47,17 @@ impl ResolverWalk<Expr> for Resolver<'_> {
                 for arg in call_expr.arguments.iter() {
                     self.resolve(arg);
                 }
+                for arg in call_expr.named.iter() {
+                    self.resolve(&arg.value);
+                }
+                self.check_arity(call_expr);
+            }
+            Expr::Index(index_expr) => {
+                self.resolve(&index_expr.object);
+                self.resolve(&index_expr.index);
+            }
+            Expr::Spread(spread_expr) => {
+                self.resolve(&spread_expr.expression);
             }
             Expr::Unary(unary_expr) => {
                 self.resolve(&unary_expr.right);
@@ -55,8 +75,13 @@ impl ResolverWalk<Expr> for Resolver<'_> {
                         this_expr.keyword.clone(),
                         "Can't use 'this' outside of a class.".to_string(),
                     );
+                } else if self.in_static_method {
+                    my_error_token(
+                        this_expr.keyword.clone(),
+                        "Can't use 'this' in a static method.".to_string(),
+                    );
                 }
-                self.resolve_local(expr, &this_expr.keyword.lexeme);
+                self.resolve_local(expr, &this_expr.keyword.lexeme, true);
             }
             Expr::Super(super_expr) => {
                 if self.is_class_none() {
@@ -65,14 +90,20 @@ impl ResolverWalk<Expr> for Resolver<'_> {
                         "Can't use 'super' outside of a class.".to_string(),
                     );
                 }
-                if !self.is_subclass() {
+                // trait 里的 super 指混入它的那个类的超类，要到运行时才知道有没有
+                if !self.is_subclass() && !self.is_trait() {
                     my_error_token(
                         super_expr.keyword.clone(),
                         "Can't use 'super' in a class with no superclass.".to_string(),
                     );
+                } else if self.in_static_method {
+                    my_error_token(
+                        super_expr.keyword.clone(),
+                        "Can't use 'super' in a static method.".to_string(),
+                    );
                 }
 
-                self.resolve_local(expr, &super_expr.keyword.lexeme);
+                self.resolve_local(expr, &super_expr.keyword.lexeme, true);
             }
         }
     }
@@ -88,7 +119,7 @@ impl ResolverWalk<Stmt> for Resolver<'_> {
                 self.resolve(&print_stmt.expression);
             }
             Stmt::Var(var_stmt) => {
-                self.declare(&var_stmt.name);
+                self.declare(&var_stmt.name, BindingKind::Variable);
                 if let Some(initializer) = &var_stmt.initializer {
                     self.resolve(initializer);
                 }
@@ -110,9 +141,27 @@ impl ResolverWalk<Stmt> for Resolver<'_> {
                 self.resolve(&while_stmt.condition);
                 self.resolve(&while_stmt.body);
             }
+            Stmt::For(for_stmt) => {
+                self.begin_scope();
+                if let Some(initializer) = &for_stmt.initializer {
+                    self.resolve(initializer);
+                }
+                if let Some(condition) = &for_stmt.condition {
+                    self.resolve(condition);
+                }
+                if let Some(increment) = &for_stmt.increment {
+                    self.resolve(increment);
+                }
+                self.resolve(&for_stmt.body);
+                self.end_scope();
+            }
+            Stmt::DoWhile(do_while_stmt) => {
+                self.resolve(&do_while_stmt.body);
+                self.resolve(&do_while_stmt.condition);
+            }
             Stmt::Function(function_stmt) => {
-                self.declare(&function_stmt.name);
-                self.define(&function_stmt.name);
+                self.declare(&function_stmt.name, BindingKind::Function);
+                self.define_callable(&function_stmt.name, stmt);
                 self.resolve_function(function_stmt, FunctionType::Function);
                 // self.begin_scope();
                 // for param in function_stmt.params.iter() {
@@ -144,8 +193,11 @@ impl ResolverWalk<Stmt> for Resolver<'_> {
                 let has_superclass = class_stmt.superclass.is_some();
                 let enclosing_class = self.class_type;
                 self.set_class_type(ClassType::Class);
-                self.declare(&class_stmt.name);
-                self.define(&class_stmt.name);
+                self.declare(&class_stmt.name, BindingKind::Class);
+                self.define_callable(&class_stmt.name, stmt);
+                for trait_expr in class_stmt.traits.iter() {
+                    self.resolve(trait_expr);
+                }
 
                 if has_superclass {
                     let superclass_name = class_stmt.superclass_name().unwrap();
@@ -161,10 +213,18 @@ impl ResolverWalk<Stmt> for Resolver<'_> {
                     self.set_class_type(ClassType::Subclass);
                     self.resolve(class_stmt.superclass.as_ref().unwrap());
                     self.begin_scope();
-                    self.cur_scope().unwrap().insert("super".to_string(), true);
+                    self.cur_scope()
+                        .unwrap()
+                        .insert("super".to_string(), Binding::implicit());
+                }
+                // 静态方法的闭包里只有 super，没有 this
+                for function in class_stmt.class_methods.iter() {
+                    self.resolve_function(function.as_ref(), FunctionType::StaticMethod);
                 }
                 self.begin_scope();
-                self.cur_scope().unwrap().insert("this".to_string(), true);
+                self.cur_scope()
+                    .unwrap()
+                    .insert("this".to_string(), Binding::implicit());
 
                 for function in class_stmt.methods.iter() {
                     let function_type = if function.name.lexeme == "init" {
@@ -181,6 +241,36 @@ impl ResolverWalk<Stmt> for Resolver<'_> {
                 }
                 self.set_class_type(enclosing_class);
             }
+            Stmt::Trait(trait_stmt) => {
+                let enclosing_class = self.class_type;
+                self.set_class_type(ClassType::Trait);
+                self.declare(&trait_stmt.name, BindingKind::Class);
+                self.define(&trait_stmt.name);
+
+                // 和有超类的类一样，方法外面先有一层 super，混入时再绑定
+                self.begin_scope();
+                self.cur_scope()
+                    .unwrap()
+                    .insert("super".to_string(), Binding::implicit());
+                for function in trait_stmt.class_methods.iter() {
+                    self.resolve_function(function.as_ref(), FunctionType::StaticMethod);
+                }
+                self.begin_scope();
+                self.cur_scope()
+                    .unwrap()
+                    .insert("this".to_string(), Binding::implicit());
+                for function in trait_stmt.methods.iter() {
+                    let function_type = if function.name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(function.as_ref(), function_type);
+                }
+                self.end_scope();
+                self.end_scope();
+                self.set_class_type(enclosing_class);
+            }
         }
     }
 }
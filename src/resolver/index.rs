@@ -0,0 +1,85 @@
+use crate::token::Token;
+
+use super::BindingKind;
+
+// 行号从 1 开始、列从 0 开始，和 Token 一致
+pub type Position = (usize, usize);
+
+// Resolver 顺带记录的符号信息，lsp 的跳转、引用、悬停和补全都查它
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    pub definitions: Vec<Definition>,
+    // 引用处的 token 和它指向的 definitions 下标
+    pub references: Vec<(Token, usize)>,
+    pub scopes: Vec<ScopeRange>,
+}
+
+#[derive(Debug)]
+pub struct Definition {
+    pub name: Token,
+    pub kind: BindingKind,
+    pub global: bool,
+}
+
+// 局部作用域大致覆盖的范围：从进入前最后一个名字到里面最后一个名字所在行的行尾
+#[derive(Debug)]
+pub struct ScopeRange {
+    pub start: Position,
+    pub end: Position,
+    pub definitions: Vec<usize>,
+}
+
+pub fn token_start(token: &Token) -> Position {
+    (token.line, token.column)
+}
+pub fn token_end(token: &Token) -> Position {
+    (token.line, token.column + token.lexeme.len())
+}
+pub fn contains(token: &Token, position: Position) -> bool {
+    token_start(token) <= position && position <= token_end(token)
+}
+
+impl SymbolIndex {
+    // 光标处的名字对应的声明：光标在声明本身上也算
+    pub fn definition_at(&self, position: Position) -> Option<usize> {
+        if let Some(i) = self
+            .definitions
+            .iter()
+            .position(|d| contains(&d.name, position))
+        {
+            return Some(i);
+        }
+        self.references
+            .iter()
+            .find(|(token, _)| contains(token, position))
+            .map(|(_, i)| *i)
+    }
+    // 声明和所有引用的位置
+    pub fn references_to(&self, definition: usize) -> Vec<&Token> {
+        let mut tokens = vec![&self.definitions[definition].name];
+        tokens.extend(
+            self.references
+                .iter()
+                .filter(|(_, i)| *i == definition)
+                .map(|(token, _)| token),
+        );
+        tokens
+    }
+    // 在光标处可见的名字：全局的，加上光标所在作用域里光标之前声明的
+    pub fn visible_at(&self, position: Position) -> Vec<&Definition> {
+        let mut visible: Vec<_> = self.definitions.iter().filter(|d| d.global).collect();
+        for scope in &self.scopes {
+            if scope.start > position || (scope.end.0, usize::MAX) < position {
+                continue;
+            }
+            visible.extend(
+                scope
+                    .definitions
+                    .iter()
+                    .map(|i| &self.definitions[*i])
+                    .filter(|d| token_start(&d.name) < position),
+            );
+        }
+        visible
+    }
+}
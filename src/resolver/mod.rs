@@ -1,6 +1,8 @@
 mod interface;
 mod resolver;
 mod impls;
+mod index;
 
 pub use interface::*;
 pub use resolver::*;
+pub use index::*;
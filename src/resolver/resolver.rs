@@ -1,32 +1,83 @@
 use std::collections::HashMap;
 
 use crate::{
-    error::my_error_token,
+    callable::Arity,
+    environment::Environment,
+    error::{my_error_token, warning_token},
     evaluator::Evaluator,
-    expr::Expr,
+    expr::{call::CallExpr, Expr},
     stmt::{function::FunctionStmt, Stmt},
     token::Token,
 };
 
-use super::ResolverWalk;
+use super::{token_end, Definition, Position, ResolverWalk, ScopeRange, SymbolIndex};
 #[derive(Clone, Copy, Debug)]
 pub enum FunctionType {
     None,
     Function,
     Initializer,
     Method,
+    StaticMethod,
 }
 #[derive(Clone, Copy, Debug)]
 pub enum ClassType {
     None,
     Class,
     Subclass,
+    Trait,
+}
+// 局部绑定的种类，决定未使用警告的措辞
+#[derive(Clone, Copy, Debug)]
+pub enum BindingKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    // this/super 这类隐式绑定，从不警告
+    Implicit,
+}
+#[derive(Debug)]
+pub struct Binding {
+    // false 表示已声明但初始化还没结束
+    pub defined: bool,
+    pub used: bool,
+    pub kind: BindingKind,
+    pub token: Option<Token>,
+    // 直接绑定到函数声明或类时的参数个数，被赋值过就不再可信
+    pub arity: Option<Arity>,
+    // 记录符号时在 SymbolIndex::definitions 里的下标
+    pub symbol: Option<usize>,
+    // 在作用域里的声明顺序，也就是运行时的 slot 下标；警告按它排序输出
+    order: usize,
+}
+impl Binding {
+    pub fn implicit() -> Self {
+        Self {
+            defined: true,
+            used: false,
+            kind: BindingKind::Implicit,
+            token: None,
+            arity: None,
+            symbol: None,
+            order: 0,
+        }
+    }
 }
 pub struct Resolver<'a> {
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Binding>>,
     evaluator: &'a mut Evaluator,
     pub function_type: FunctionType,
     pub class_type: ClassType,
+    // 静态方法（包括里面嵌套的函数）没有 this/super
+    pub in_static_method: bool,
+    // resolve_program 收集的全局名字和已知的参数个数；None 时不检查未定义的全局变量
+    globals: Option<HashMap<String, Option<Arity>>>,
+    // with_index 打开后记录声明和引用
+    index: Option<SymbolIndex>,
+    global_symbols: HashMap<String, usize>,
+    scope_starts: Vec<Position>,
+    // 最近处理过的名字的结束位置，用来估计作用域范围
+    last_pos: Position,
 }
 
 impl<'a> Resolver<'a> {
@@ -36,6 +87,48 @@ impl<'a> Resolver<'a> {
             evaluator,
             function_type: FunctionType::None,
             class_type: ClassType::None,
+            in_static_method: false,
+            globals: None,
+            index: None,
+            global_symbols: HashMap::new(),
+            scope_starts: vec![],
+            last_pos: (0, 0),
+        }
+    }
+    // 记录符号信息，resolve 完用 take_index 取出
+    pub fn with_index(mut self) -> Self {
+        self.index = Some(SymbolIndex::default());
+        self
+    }
+    pub fn take_index(&mut self) -> Option<SymbolIndex> {
+        self.index.take()
+    }
+    fn add_definition(&mut self, name: &Token, kind: BindingKind, global: bool) -> Option<usize> {
+        let index = self.index.as_mut()?;
+        index.definitions.push(Definition {
+            name: name.clone(),
+            kind,
+            global,
+        });
+        Some(index.definitions.len() - 1)
+    }
+    // 变量引用指向哪个声明，局部的看绑定，全局的看顶层声明
+    pub fn record_reference(&mut self, token: &Token) {
+        self.last_pos = token_end(token);
+        if self.index.is_none() {
+            return;
+        }
+        let local = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&token.lexeme));
+        let symbol = match local {
+            Some(binding) => binding.symbol,
+            None => self.global_symbols.get(&token.lexeme).copied(),
+        };
+        if let (Some(symbol), Some(index)) = (symbol, self.index.as_mut()) {
+            index.references.push((token.clone(), symbol));
         }
     }
     pub fn set_function_type(&mut self, function_type: FunctionType) {
@@ -67,39 +160,132 @@ impl<'a> Resolver<'a> {
     pub fn is_subclass(&self) -> bool {
         matches!(self.class_type, ClassType::Subclass)
     }
+    pub fn is_trait(&self) -> bool {
+        matches!(self.class_type, ClassType::Trait)
+    }
     pub fn resolve_function(&mut self, function: &FunctionStmt, function_type: FunctionType) {
         let enclosing_function = self.function_type;
+        let enclosing_static = self.in_static_method;
         self.set_function_type(function_type);
+        // 嵌套的普通函数沿用外层；进入另一个类的实例方法时又有 this 了
+        match function_type {
+            FunctionType::StaticMethod => self.in_static_method = true,
+            FunctionType::Method | FunctionType::Initializer => self.in_static_method = false,
+            _ => {}
+        }
 
         // self.declare(&function.name);
         // self.define(&function.name);
         self.begin_scope();
         for param in function.params.iter() {
-            self.declare(param);
-            self.define(param);
+            if let Some(default) = &param.default {
+                self.resolve(default);
+            }
+            self.declare(&param.name, BindingKind::Parameter);
+            self.define(&param.name);
+        }
+        if let Some(rest) = &function.rest {
+            self.declare(rest, BindingKind::Parameter);
+            self.define(rest);
         }
         self.resolve_stmts(&function.fn_body);
         self.end_scope();
 
         self.set_function_type(enclosing_function);
+        self.in_static_method = enclosing_static;
     }
-    pub fn resolve_stmts(&mut self, stmts: &Vec<Stmt>) {
+    // 先收集所有顶层声明，函数体里可以引用后面才定义的全局变量；没声明过的名字只给警告
+    pub fn resolve_program(&mut self, stmts: &[Stmt]) {
+        let natives = Environment::global_env();
+        let natives = natives.borrow();
+        let mut globals: HashMap<_, _> = natives
+            .names()
+            .into_iter()
+            .map(|name| {
+                let arity = natives.get(&name).ok();
+                let arity = arity.and_then(|value| value.as_callable().map(|c| c.arity()));
+                (name, arity)
+            })
+            .collect();
+        for stmt in stmts {
+            let name = match stmt {
+                Stmt::Var(var) => &var.name,
+                Stmt::Function(function) => &function.name,
+                Stmt::Class(class) => &class.name,
+                Stmt::Trait(trait_stmt) => &trait_stmt.name,
+                _ => continue,
+            };
+            // 重复声明的名字调用时指向哪个要看执行到哪里
+            let arity = match globals.contains_key(&name.lexeme) {
+                true => None,
+                false => static_arity(stmt),
+            };
+            let kind = match stmt {
+                Stmt::Var(_) => BindingKind::Variable,
+                Stmt::Function(_) => BindingKind::Function,
+                _ => BindingKind::Class,
+            };
+            if let Some(symbol) = self.add_definition(name, kind, true) {
+                self.global_symbols
+                    .entry(name.lexeme.clone())
+                    .or_insert(symbol);
+            }
+            globals.insert(name.lexeme.clone(), arity);
+        }
+        self.globals = Some(globals);
+        self.resolve_stmts(stmts);
+    }
+    pub fn resolve_stmts(&mut self, stmts: &[Stmt]) {
         for stmt in stmts {
             self.resolve(stmt);
         }
     }
     pub fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.scope_starts.push(self.last_pos);
     }
     pub fn end_scope(&mut self) {
-        self.scopes.pop();
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        let start = self.scope_starts.pop().unwrap_or_default();
+        if let Some(index) = self.index.as_mut() {
+            index.scopes.push(ScopeRange {
+                start,
+                end: self.last_pos,
+                definitions: scope
+                    .values()
+                    .filter_map(|binding| binding.symbol)
+                    .collect(),
+            });
+        }
+        // 以 `_` 开头的名字表示故意不用
+        let mut unused: Vec<_> = scope
+            .into_iter()
+            .filter(|(name, binding)| !binding.used && !name.starts_with('_'))
+            .filter_map(|(_, binding)| Some((binding.order, binding.kind, binding.token?)))
+            .collect();
+        unused.sort_by_key(|(order, ..)| *order);
+        for (_, kind, token) in unused {
+            let what = match kind {
+                BindingKind::Variable => "Local variable",
+                BindingKind::Parameter => "Parameter",
+                BindingKind::Function => "Local function",
+                BindingKind::Class => "Local class",
+                BindingKind::Implicit => continue,
+            };
+            let message = format!("{} '{}' is never used.", what, token.lexeme);
+            warning_token(token, message);
+        }
     }
-    pub fn declare(&mut self, token: &Token) {
+    pub fn declare(&mut self, token: &Token, kind: BindingKind) {
+        self.last_pos = token_end(token);
         let name = token.lexeme.clone();
         let len = self.scopes.len();
         if len == 0 {
             return;
         }
+        let symbol = self.add_definition(token, kind, false);
         let scope = &mut self.scopes[len - 1];
         if scope.contains_key(&name) {
             my_error_token(
@@ -107,7 +293,16 @@ impl<'a> Resolver<'a> {
                 "Already a variable with this name in this scope.".to_string(),
             );
         }
-        scope.insert(name, false);
+        let binding = Binding {
+            defined: false,
+            used: false,
+            kind,
+            token: Some(token.clone()),
+            arity: None,
+            symbol,
+            order: scope.len(),
+        };
+        scope.insert(name, binding);
     }
     pub fn define(&mut self, token: &Token) {
         let name = token.lexeme.clone();
@@ -115,18 +310,81 @@ impl<'a> Resolver<'a> {
         if len == 0 {
             return;
         }
-        // 不需要检查是否已经存在，因为定义时，变量的声明不一定在当前作用域
-        self.scopes[len - 1].insert(name, true);
+        // 定义前一定在当前作用域声明过
+        if let Some(binding) = self.scopes[len - 1].get_mut(&name) {
+            binding.defined = true;
+        }
+    }
+    // 函数和类声明之后记下参数个数
+    pub fn define_callable(&mut self, token: &Token, stmt: &Stmt) {
+        self.define(token);
+        let arity = static_arity(stmt);
+        if let Some(binding) = self
+            .cur_scope()
+            .and_then(|scope| scope.get_mut(&token.lexeme))
+        {
+            binding.arity = arity;
+        }
     }
-    pub fn resolve_local(&mut self, expr: &Expr, name: &str) {
-        for (i, item) in self.scopes.iter().rev().enumerate() {
-            if item.contains_key(name) {
-                self.evaluator.resolve(expr, i);
-                return;
+    // 记录局部变量的深度；is_read 为 false 时（赋值）不算使用。返回 false 表示是全局变量
+    pub fn resolve_local(&mut self, expr: &Expr, name: &str, is_read: bool) -> bool {
+        for (i, item) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(binding) = item.get_mut(name) {
+                binding.used |= is_read;
+                if !is_read {
+                    binding.arity = None;
+                }
+                self.evaluator.resolve(expr, i, binding.order);
+                return true;
             }
         }
+        false
     }
-    pub fn cur_scope(&mut self) -> Option<&mut HashMap<String, bool>> {
+    // 不是局部变量，也不在任何顶层声明和内置函数里；给全局变量赋值后不再知道它的参数个数
+    pub fn check_global(&mut self, name: &Token, is_read: bool) {
+        let Some(globals) = &mut self.globals else {
+            return;
+        };
+        match globals.get_mut(&name.lexeme) {
+            Some(arity) if !is_read => *arity = None,
+            Some(_) => {}
+            None => {
+                let message = format!("Undefined variable '{}'.", name.lexeme);
+                warning_token(name.clone(), message);
+            }
+        }
+    }
+    // 调用的是已知的函数、类或内置函数时提前检查参数个数，和运行时一样只给警告
+    pub fn check_arity(&self, call: &CallExpr) {
+        let Some(callee) = call.callee.to_variable() else {
+            return;
+        };
+        // 展开参数和具名参数的个数要到运行时才知道
+        let has_spread = call
+            .arguments
+            .iter()
+            .any(|arg| matches!(arg, Expr::Spread(_)));
+        if has_spread || !call.named.is_empty() {
+            return;
+        }
+        let name = &callee.name.lexeme;
+        let arity = match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(binding) => binding.arity,
+            None => self
+                .globals
+                .as_ref()
+                .and_then(|globals| globals.get(name).copied().flatten()),
+        };
+        let Some(arity) = arity else {
+            return;
+        };
+        let count = call.arguments.len();
+        if !arity.accepts(count) {
+            let message = format!("Expected {} arguments but got {}.", arity, count);
+            warning_token(call.parent.clone(), message);
+        }
+    }
+    pub fn cur_scope(&mut self) -> Option<&mut HashMap<String, Binding>> {
         let len = self.scopes.len();
         if len == 0 {
             return None;
@@ -134,3 +392,139 @@ impl<'a> Resolver<'a> {
         Some(&mut self.scopes[len - 1])
     }
 }
+
+// 函数声明的参数个数；类看自己的 init，没有 init 也没有超类和 trait 时不接受参数
+fn static_arity(stmt: &Stmt) -> Option<Arity> {
+    match stmt {
+        Stmt::Function(function) => Some(function.arity()),
+        Stmt::Class(class) => match class.methods.iter().find(|m| m.name.lexeme == "init") {
+            Some(init) => Some(init.arity()),
+            None if class.superclass.is_none() && class.traits.is_empty() => Some(Arity::exact(0)),
+            None => None,
+        },
+        _ => None,
+    }
+}
+
+#[test]
+fn static_method_scope_ends_at_nested_instance_methods() {
+    use crate::test_util::{report_lines, resolve_source};
+
+    let src = r#"
+class Outer {
+  class make() {
+    class Inner { hi() { return this; } }
+    return Inner();
+  }
+}
+"#;
+    assert!(report_lines(&resolve_source(src)).is_empty());
+    // 静态方法里的普通函数仍然没有 this
+    let src = r#"
+class Outer {
+  class make() {
+    fun helper() { return this; }
+    return helper;
+  }
+}
+"#;
+    assert_eq!(
+        report_lines(&resolve_source(src)),
+        ["[line 4] Can't use 'this' in a static method."]
+    );
+}
+
+#[test]
+fn warns_about_unused_locals() {
+    use crate::test_util::{report_lines, resolve_source};
+
+    let src = r#"
+fun f(a, _b, c) {
+  var unused = 1;
+  var _skipped = 2;
+  var written = 3;
+  written = 4;
+  fun helper() {}
+  return c;
+}
+{ class Local {} }
+var global = 1;
+"#;
+    let reports = resolve_source(src);
+    // 警告不影响退出码
+    assert!(reports.iter().all(|report| report.warning));
+    assert_eq!(
+        report_lines(&reports),
+        [
+            "[line 2] Parameter 'a' is never used.",
+            "[line 3] Local variable 'unused' is never used.",
+            "[line 5] Local variable 'written' is never used.",
+            "[line 7] Local function 'helper' is never used.",
+            "[line 10] Local class 'Local' is never used.",
+        ]
+    );
+}
+
+#[test]
+fn warns_about_undefined_globals() {
+    use crate::test_util::{report_lines, resolve_source};
+
+    // 函数体里用到后面才定义的全局变量不算未定义，内置函数也不算
+    let src = r#"
+fun show() { print later + clock(); }
+var later = 1;
+show();
+print missing;
+missing = 2;
+fun f() { return typo; }
+"#;
+    let reports = resolve_source(src);
+    assert!(reports.iter().all(|report| report.warning));
+    assert_eq!(
+        report_lines(&reports),
+        [
+            "[line 5] Undefined variable 'missing'.",
+            "[line 6] Undefined variable 'missing'.",
+            "[line 7] Undefined variable 'typo'.",
+        ]
+    );
+}
+
+#[test]
+fn warns_about_wrong_argument_counts() {
+    use crate::test_util::{report_lines, resolve_source};
+
+    let src = r#"
+fun add(a, b) { return a + b; }
+add(1);
+add(1, 2);
+class Point { init(x, y) { this.x = x; this.y = y; } }
+Point(1, 2, 3);
+clock(1);
+fun opt(a, b = 1) { return a + b; }
+opt();
+opt(1);
+var reassigned = add;
+reassigned = clock;
+reassigned(1);
+{
+  fun local(x) { return x; }
+  local();
+}
+add(...reassigned);
+opt(a: 1);
+"#;
+    let reports = resolve_source(src);
+    assert!(reports.iter().all(|report| report.warning));
+    // 重新赋值过的变量、展开参数和具名参数到运行时才知道
+    assert_eq!(
+        report_lines(&reports),
+        [
+            "[line 3] Expected 2 arguments but got 1.",
+            "[line 6] Expected 2 arguments but got 3.",
+            "[line 7] Expected 0 arguments but got 1.",
+            "[line 9] Expected 1 to 2 arguments but got 0.",
+            "[line 16] Expected 1 arguments but got 0.",
+        ]
+    );
+}
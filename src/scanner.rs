@@ -16,6 +16,10 @@ pub struct Scanner {
     line: usize,
     start: usize,
     current: usize,
+    keep_comments: bool,
+    // 已经 flush 掉的字节数，以及当前行开头的绝对位置，用来算列号
+    offset: usize,
+    line_start: usize,
 }
 
 impl Scanner {
@@ -26,6 +30,16 @@ impl Scanner {
             start: 0,
             current: 0,
             tokens: vec![],
+            keep_comments: false,
+            offset: 0,
+            line_start: 0,
+        }
+    }
+    // 保留注释 token，parser 不认识 COMMENT，只给 fmt 用
+    pub fn with_comments(source: Bytes) -> Self {
+        Self {
+            keep_comments: true,
+            ..Self::new(source)
         }
     }
     pub fn tokens(&self) -> Vec<Token> {
@@ -52,7 +66,14 @@ impl Scanner {
             b')' => RightParen,
             b'{' => LeftBrace,
             b'}' => RightBrace,
+            b'[' => LeftBracket,
+            b']' => RightBracket,
             b',' => COMMA,
+            b':' => COLON,
+            b'.' if self.peek() == Some(b'.') && self.peek_next(1) == Some(b'.') => {
+                self.current += 2;
+                DotDotDot
+            }
             b'.' => DOT,
             b'-' => MINUS,
             b'+' => PLUS,
@@ -87,12 +108,23 @@ impl Scanner {
                     GREATER
                 }
             }
+            b'?' if self.match_advance(b'.') => QuestionDot,
+            b'?' if self.match_advance(b'?') => QuestionQuestion,
             b'/' => {
                 if self.match_advance(b'/') {
-                    while !self.is_at_end() && self.advance_unchecked() != b'\n' {}
-                    self.line += 1;
-                    self.flush();
-                    return Ok(());
+                    if self.keep_comments {
+                        while self.peek().is_some_and(|b| b != b'\n') {
+                            self.current += 1;
+                        }
+                        let text = self.source.slice(self.start..self.current);
+                        COMMENT(String::from_utf8_lossy(&text).trim_end().to_string())
+                    } else {
+                        while !self.is_at_end() && self.advance_unchecked() != b'\n' {}
+                        self.line += 1;
+                        self.flush();
+                        self.line_start = self.offset;
+                        return Ok(());
+                    }
                 } else {
                     SLASH
                 }
@@ -100,6 +132,7 @@ impl Scanner {
             b'\n' => {
                 self.line += 1;
                 self.flush();
+                self.line_start = self.offset;
                 return Ok(());
             }
             b'"' => {
@@ -156,18 +189,22 @@ impl Scanner {
     }
     fn flush(&mut self) -> String {
         let lexeme = self.source.slice(self.start..self.current);
+        self.offset += self.current;
         self.source.advance(self.current);
         self.current = 0;
         self.start = 0;
         String::from_utf8(lexeme.to_vec()).unwrap()
     }
     fn add_token(&mut self, token_type: TokenType) {
+        // 跨行的字符串算在结束的那一行，列号取 0
+        let column = self.offset.saturating_sub(self.line_start);
         let lexeme = self.flush();
 
         self.tokens.push(Token {
             t_type: token_type,
             lexeme,
             line: self.line,
+            column,
         });
     }
     fn string(&mut self) -> Option<TokenType> {
@@ -179,6 +216,7 @@ impl Scanner {
             };
             if b == b'\n' {
                 self.line += 1;
+                self.line_start = self.offset + self.current + 1;
             }
             self.current += 1;
             if b == b'"' {
@@ -1,19 +1,27 @@
 use super::Stmt;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct BlockStmt {
     pub statements: Vec<Stmt>,
+    // `{` 所在行，空块没有别的 token 可以定位；脱糖生成的块为 0
+    #[serde(default)]
+    pub line: usize,
 }
 impl From<Vec<Stmt>> for BlockStmt {
     fn from(statements: Vec<Stmt>) -> Self {
-        Self { statements }
+        Self {
+            statements,
+            line: 0,
+        }
     }
 }
 impl BlockStmt {
     pub fn from(arr: impl IntoIterator<Item = Stmt>) -> Self {
         Self {
             statements: arr.into_iter().collect(),
+            line: 0,
         }
     }
     pub fn push(&mut self, value: Stmt) {
@@ -1,5 +1,7 @@
 use std::rc::Rc;
 
+use serde::{Deserialize, Serialize};
+
 
 use crate::{
     expr::Expr,
@@ -8,11 +10,26 @@ use crate::{
 
 use super::function::FunctionStmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ClassStmt {
     pub name: Token,
     pub methods: Vec<Rc<FunctionStmt>>,
+    // `class square(n) { ... }`，通过类对象调用
+    #[serde(default)]
+    pub class_methods: Vec<Rc<FunctionStmt>>,
     pub superclass: Option<Expr>,
+    // `with A, B` 混入的 trait，都是 VariableExpr
+    #[serde(default)]
+    pub traits: Vec<Expr>,
+    // `var x: Number;` 字段声明，只给 check 用
+    #[serde(default)]
+    pub fields: Vec<FieldDecl>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FieldDecl {
+    pub name: Token,
+    pub type_ann: Option<Token>,
 }
 
 impl ClassStmt {
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{expr::Expr, token::Token};
+
+use super::Stmt;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DoWhileStmt {
+    pub keyword: Token,
+    pub body: Stmt,
+    pub condition: Expr,
+}
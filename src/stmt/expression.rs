@@ -1,7 +1,8 @@
+use serde::{Deserialize, Serialize};
 use crate::expr::Expr;
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct ExpressionStmt {
     pub expression: Expr,
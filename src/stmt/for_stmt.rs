@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    expr::{literal::LiteralExpr, Expr},
+    token::Token,
+};
+
+use super::{block::BlockStmt, expression::ExpressionStmt, while_stmt::WhileStmt, Stmt};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForStmt {
+    pub keyword: Token,
+    pub initializer: Option<Stmt>,
+    pub condition: Option<Expr>,
+    pub increment: Option<Expr>,
+    pub body: Stmt,
+}
+
+impl ForStmt {
+    // 脱糖成 `{ initializer; while (condition) { body; increment; } }`
+    pub fn lower(&self) -> Stmt {
+        let condition = self
+            .condition
+            .clone()
+            .unwrap_or_else(|| LiteralExpr::from(true).into());
+        let mut body = BlockStmt::from([self.body.clone()]);
+        if let Some(increment) = &self.increment {
+            body.push(ExpressionStmt::from(increment.clone()).into());
+        }
+        let while_stmt: Stmt = WhileStmt {
+            condition,
+            body: body.into(),
+            line: self.keyword.line,
+        }
+        .into();
+        match &self.initializer {
+            Some(init) => BlockStmt::from([init.clone(), while_stmt]).into(),
+            None => while_stmt,
+        }
+    }
+}
@@ -1,15 +1,43 @@
-use crate::token::Token;
+use serde::{Deserialize, Serialize};
+use crate::{callable::Arity, expr::Expr, token::Token};
 
 use super::Stmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct FunctionStmt {
     pub name:Token,
-    pub params: Vec<Token>,
+    pub params: Vec<Param>,
+    // `fun f(a, ...rest)`，多出来的参数收集成 list
+    #[serde(default)]
+    pub rest: Option<Token>,
     pub fn_body: Vec<Stmt>,
+    // 没有参数列表的方法，访问属性时自动调用
+    #[serde(default)]
+    pub getter: bool,
+    // `fun f(): Number`
+    #[serde(default)]
+    pub return_type: Option<Token>,
 }
 
 impl FunctionStmt {
+    // 没有默认值的参数个数，默认值参数只能出现在它们后面
+    pub fn required_params(&self) -> usize {
+        self.params.iter().take_while(|p| p.default.is_none()).count()
+    }
+    pub fn arity(&self) -> Arity {
+        Arity {
+            min: self.required_params(),
+            max: self.rest.is_none().then_some(self.params.len()),
+        }
+    }
+}
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Param {
+    pub name: Token,
+    // 调用时在函数的闭包里求值
+    pub default: Option<Expr>,
+    #[serde(default)]
+    pub type_ann: Option<Token>,
 }
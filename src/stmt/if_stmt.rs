@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use crate::expr::Expr;
 
 use super::Stmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct IfStmt {
     pub then_branch: Stmt,
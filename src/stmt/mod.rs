@@ -1,26 +1,34 @@
 use std::rc::Rc;
 
+use serde::{Deserialize, Serialize};
+
 use block::BlockStmt;
 use class_stmt::ClassStmt;
+use do_while_stmt::DoWhileStmt;
 use expression::ExpressionStmt;
+use for_stmt::ForStmt;
 use function::FunctionStmt;
 use if_stmt::IfStmt;
 use print::PrintStmt;
 use return_stmt::ReturnStmt;
+use trait_stmt::TraitStmt;
 use var::VarStmt;
 use while_stmt::WhileStmt;
 
 pub mod block;
 pub mod class_stmt;
+pub mod do_while_stmt;
 pub mod expression;
+pub mod for_stmt;
 pub mod function;
 pub mod if_stmt;
 pub mod print;
 pub mod return_stmt;
+pub mod trait_stmt;
 pub mod var;
 pub mod while_stmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Stmt {
     Var(Box<VarStmt>),
     Expression(Box<ExpressionStmt>),
@@ -28,9 +36,12 @@ pub enum Stmt {
     Print(Box<PrintStmt>),
     If(Box<IfStmt>),
     While(Box<WhileStmt>),
+    For(Box<ForStmt>),
+    DoWhile(Box<DoWhileStmt>),
     Function(Rc<FunctionStmt>), // 使用 Rc 避免 Clone 函数
     Return(Box<ReturnStmt>),
     Class(ClassStmt),
+    Trait(TraitStmt),
 }
 impl Stmt {
     pub fn as_function(&self) -> Option<&FunctionStmt> {
@@ -70,6 +81,16 @@ impl From<WhileStmt> for Stmt {
         Self::While(value.into())
     }
 }
+impl From<ForStmt> for Stmt {
+    fn from(value: ForStmt) -> Self {
+        Self::For(value.into())
+    }
+}
+impl From<DoWhileStmt> for Stmt {
+    fn from(value: DoWhileStmt) -> Self {
+        Self::DoWhile(value.into())
+    }
+}
 impl From<FunctionStmt> for Stmt {
     fn from(value: FunctionStmt) -> Self {
         Self::Function(Rc::new(value))
@@ -85,3 +106,8 @@ impl From<ClassStmt> for Stmt {
         Self::Class(value)
     }
 }
+impl From<TraitStmt> for Stmt {
+    fn from(value: TraitStmt) -> Self {
+        Self::Trait(value)
+    }
+}
@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use crate::{expr::Expr, token::Token};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReturnStmt {
     pub keyword: Token,
     pub value: Option<Expr>,
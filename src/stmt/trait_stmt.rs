@@ -0,0 +1,16 @@
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::token::Token;
+
+use super::function::FunctionStmt;
+
+// `trait Printable { ... }`，方法在类声明时混入 `class Foo with Printable`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraitStmt {
+    pub name: Token,
+    pub methods: Vec<Rc<FunctionStmt>>,
+    #[serde(default)]
+    pub class_methods: Vec<Rc<FunctionStmt>>,
+}
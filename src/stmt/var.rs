@@ -1,8 +1,12 @@
+use serde::{Deserialize, Serialize};
 use crate::{expr::Expr, token::Token};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct VarStmt {
     pub name: Token,
     pub initializer: Option<Expr>,
+    // `var x: Number`，只给 check 用，求值时忽略
+    #[serde(default)]
+    pub type_ann: Option<Token>,
 }
@@ -1,10 +1,14 @@
 use crate::expr::Expr;
+use serde::{Deserialize, Serialize};
 
 use super::Stmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct WhileStmt {
     pub condition: Expr,
     pub body: Stmt,
+    // `while` 所在行，条件可能是没有位置信息的字面量
+    #[serde(default)]
+    pub line: usize,
 }
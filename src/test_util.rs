@@ -0,0 +1,64 @@
+// 测试共用的 扫描 → 解析 → resolve → 执行 流程
+
+use bytes::Bytes;
+
+use crate::{
+    data_types::scaler::Scalar,
+    error::{capture_reports, Report},
+    evaluator::{Evaluator, InterpretResult, Interprete},
+    parser::Parser,
+    resolver::Resolver,
+    scanner::Scanner,
+    stmt::Stmt,
+};
+
+pub fn parse_source(src: &str) -> Vec<Stmt> {
+    let mut scanner = Scanner::new(Bytes::from(src.to_string()));
+    scanner.scan_tokens().unwrap();
+    Parser::new(scanner.tokens()).parse()
+}
+
+// 解析并 resolve，返回期间报告的错误和警告
+pub fn resolve_source(src: &str) -> Vec<Report> {
+    let (_, reports) = capture_reports(|| {
+        let stmts = parse_source(src);
+        let mut evaluator = Evaluator::new(true);
+        Resolver::new(&mut evaluator).resolve_program(&stmts);
+    });
+    reports
+}
+
+// 只保留 `[line N] message` 便于断言
+pub fn report_lines(reports: &[Report]) -> Vec<String> {
+    reports
+        .iter()
+        .map(|report| format!("[line {}] {}", report.line, report.message))
+        .collect()
+}
+
+pub fn run_source(src: &str) -> (Evaluator, InterpretResult<()>) {
+    run_source_with(Evaluator::new(true), src)
+}
+
+// 用配置好的 evaluator 执行，遇到第一个错误停下
+pub fn run_source_with(mut evaluator: Evaluator, src: &str) -> (Evaluator, InterpretResult<()>) {
+    let stmts = parse_source(src);
+    if evaluator.resolver {
+        Resolver::new(&mut evaluator).resolve_program(&stmts);
+    }
+    let result = stmts.iter().try_for_each(|stmt| evaluator.eval(stmt));
+    (evaluator, result)
+}
+
+// 执行到出错为止，返回报告的运行时错误（不含警告）
+pub fn runtime_errors(src: &str) -> Vec<String> {
+    let ((_, result), mut reports) = capture_reports(|| run_source(src));
+    assert!(result.is_err(), "{}", src);
+    reports.retain(|report| !report.warning);
+    report_lines(&reports)
+}
+
+// 执行完以后全局变量的值
+pub fn global(evaluator: &Evaluator, name: &str) -> Scalar {
+    evaluator.global.borrow().get(name).unwrap()
+}
@@ -1,13 +1,17 @@
+use serde::{Deserialize, Serialize};
 
 
 use crate::token_type::{CmpTokenType, TokenType};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Token {
     pub t_type: TokenType,
     pub lexeme: String,
     // pub literal: Option<String>,
     pub line: usize,
+    // 所在行从 0 开始的字节列，给 lsp 定位用
+    #[serde(default)]
+    pub column: usize,
 }
 impl CmpTokenType<Token> for Token {
     fn is_same_type(&self, right: &Token) -> bool {
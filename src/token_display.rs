@@ -10,11 +10,15 @@ impl Display for Token {
             IDENTIFIER(s) => write!(f, "IDENTIFIER {} null", lexeme,),
             STRING(s) => write!(f, "STRING {} {}", lexeme, s),
             NUMBER(d) => write!(f, "NUMBER {} {:?}", lexeme, d),
+            COMMENT(_) => write!(f, "COMMENT {} null", lexeme),
             LeftParen => write!(f, "LEFT_PAREN {} null", lexeme),
             RightParen => write!(f, "RIGHT_PAREN {} null", lexeme),
             LeftBrace => write!(f, "LEFT_BRACE {} null", lexeme),
             RightBrace => write!(f, "RIGHT_BRACE {} null", lexeme),
+            LeftBracket => write!(f, "LEFT_BRACKET {} null", lexeme),
+            RightBracket => write!(f, "RIGHT_BRACKET {} null", lexeme),
             COMMA => write!(f, "COMMA {} null", lexeme),
+            COLON => write!(f, "COLON {} null", lexeme),
             DOT => write!(f, "DOT {} null", lexeme),
             MINUS => write!(f, "MINUS {} null", lexeme),
             PLUS => write!(f, "PLUS {} null", lexeme),
@@ -29,22 +33,29 @@ impl Display for Token {
             GreaterEqual => write!(f, "GREATER_EQUAL {} null", lexeme),
             LESS => write!(f, "LESS {} null", lexeme),
             LessEqual => write!(f, "LESS_EQUAL {} null", lexeme),
+            QuestionDot => write!(f, "QUESTION_DOT {} null", lexeme),
+            QuestionQuestion => write!(f, "QUESTION_QUESTION {} null", lexeme),
+            DotDotDot => write!(f, "DOT_DOT_DOT {} null", lexeme),
             AND => write!(f, "AND {} null", lexeme),
             CLASS => write!(f, "CLASS {} null", lexeme),
+            DO => write!(f, "DO {} null", lexeme),
             ELSE => write!(f, "ELSE {} null", lexeme),
             FALSE => write!(f, "FALSE {} null", lexeme),
             FUN => write!(f, "FUN {} null", lexeme),
             FOR => write!(f, "FOR {} null", lexeme),
             IF => write!(f, "IF {} null", lexeme),
+            IS => write!(f, "IS {} null", lexeme),
             NIL => write!(f, "NIL {} null", lexeme),
             OR => write!(f, "OR {} null", lexeme),
             PRINT => write!(f, "PRINT {} null", lexeme),
             RETURN => write!(f, "RETURN {} null", lexeme),
             SUPER => write!(f, "SUPER {} null", lexeme),
             THIS => write!(f, "THIS {} null", lexeme),
+            TRAIT => write!(f, "TRAIT {} null", lexeme),
             TRUE => write!(f, "TRUE {} null", lexeme),
             VAR => write!(f, "VAR {} null", lexeme),
             WHILE => write!(f, "WHILE {} null", lexeme),
+            WITH => write!(f, "WITH {} null", lexeme),
             EOF => write!(f, "EOF {} null", lexeme),
         }
     }
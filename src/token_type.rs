@@ -1,11 +1,15 @@
-#[derive(Clone, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     COMMA,
+    COLON,
     DOT,
     MINUS,
     PLUS,
@@ -22,29 +26,38 @@ pub enum TokenType {
     GreaterEqual,
     LESS,
     LessEqual,
+    QuestionDot,
+    QuestionQuestion,
+    DotDotDot,
 
     // Literals.
     IDENTIFIER(String),
     STRING(String),
     NUMBER(f64),
+    // 只有 Scanner::with_comments 会产生，供 fmt 使用
+    COMMENT(String),
 
     // Keywords.
     AND,
     CLASS,
+    DO,
     ELSE,
     FALSE,
     FUN,
     FOR,
     IF,
+    IS,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
     THIS,
+    TRAIT,
     TRUE,
     VAR,
     WHILE,
+    WITH,
 
     EOF,
 }